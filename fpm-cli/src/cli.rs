@@ -1,17 +1,44 @@
-use crate::{commands, utils::Result};
+use crate::{
+    commands,
+    output::{self, Column, Format},
+    utils::Result,
+};
 use clap::{command, value_parser, Arg, ArgAction, Command};
-use fpm_lib::config::Config;
+use fpm_lib::config::{Config, ConfigSources};
+use std::io::IsTerminal;
 use std::path::PathBuf;
 
 fn cli() -> Command {
     command!()
         .subcommand_required(true)
         .arg_required_else_help(true)
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .global(true)
+                .value_parser(value_parser!(PathBuf))
+                .help("Config file that overrides every other config source"),
+        )
         .subcommands(vec![
             subcommand_new(),
+            subcommand_open(),
             subcommand_add(),
+            subcommand_config(),
+            subcommand_db(),
+            subcommand_edit(),
+            subcommand_export(),
+            subcommand_import(),
+            subcommand_init(),
             subcommand_list(),
+            subcommand_move(),
+            subcommand_path(),
+            subcommand_remove(),
             subcommand_reset(),
+            subcommand_scan(),
+            subcommand_shell_init(),
+            subcommand_status(),
+            subcommand_tag(),
+            subcommand_verify(),
         ])
 }
 
@@ -36,7 +63,25 @@ fn subcommand_new() -> Command {
         Arg::new("category")
             .short('c')
             .long("category")
-            .help("Used to keep similar project types together. I.E. `work`, `thirdparty`, etc"),
+            .help("Used to keep similar project types together. I.E. `work`, `thirdparty`, etc. Defaults to `default_category` from the config"),
+        Arg::new("templates")
+            .short('t')
+            .long("template")
+            .num_args(1..)
+            .action(ArgAction::Append)
+            .help(
+                "Templates or template groups to populate the project with. Later templates overwrite files from earlier ones",
+            ),
+        Arg::new("git")
+            .short('g')
+            .long("git")
+            .conflicts_with("templates")
+            .help("Clone the project from a git repository. The name defaults to the name of the repository"),
+        Arg::new("open")
+            .short('o')
+            .long("open")
+            .help("Open the project once it has been added")
+            .action(ArgAction::SetTrue),
         Arg::new("interactive")
             .short('i')
             .long("interactive")
@@ -62,6 +107,94 @@ fn subcommand_add() -> Command {
             .short('l')
             .long("language")
             .help("Primary programming language used"),
+        Arg::new("category")
+            .short('c')
+            .long("category")
+            .help("Used to keep similar project types together. I.E. `work`, `thirdparty`, etc. Defaults to `default_category` from the config"),
+        Arg::new("open")
+            .short('o')
+            .long("open")
+            .help("Open the project once it has been added")
+            .action(ArgAction::SetTrue),
+        Arg::new("interactive")
+            .short('i')
+            .long("interactive")
+            .action(ArgAction::SetTrue),
+    ])
+}
+
+fn subcommand_config() -> Command {
+    let key = Arg::new("key")
+        .required(true)
+        .help("The config key, entries of `open`, `layouts` and `template_groups` use a dot, I.E. `open.rust`");
+
+    Command::new("config")
+        .about("Show and change the config")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommands(vec![
+            Command::new("get").about("Print the value of a key").arg(key.clone()),
+            Command::new("set").about("Set a key in the config file").args(&[
+                key.clone(),
+                Arg::new("value")
+                    .required(true)
+                    .help("The new value, read as TOML for number and list settings and as a string otherwise"),
+            ]),
+            Command::new("unset").about("Remove a key from the config file").arg(key),
+            Command::new("list").about("Print the combined config from every source"),
+            Command::new("path").about("Print the path of the config file that is changed by `set` and `edit`"),
+            Command::new("edit").about("Open the config file in $EDITOR"),
+            Command::new("validate").about("Check for unknown keys, missing directories and an unwritable database"),
+        ])
+}
+
+fn subcommand_db() -> Command {
+    Command::new("db")
+        .about("Back up and restore the project database")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommands(vec![
+            Command::new("backup")
+                .about("Save a copy of the database, this is safe while fpm is running elsewhere")
+                .args(&[Arg::new("path")
+                    .help("Where to save the backup, defaults to a timestamped file in the backup folder")
+                    .value_parser(value_parser!(PathBuf))]),
+            Command::new("restore")
+                .about("Replace the database with a backup, a snapshot of the current database is saved first")
+                .args(&[
+                    Arg::new("path")
+                        .required(true)
+                        .help("The backup to restore")
+                        .value_parser(value_parser!(PathBuf)),
+                    Arg::new("force")
+                        .short('f')
+                        .long("force")
+                        .help("Bypass conformation prompt and restore the backup")
+                        .action(ArgAction::SetTrue),
+                ]),
+            Command::new("snapshots").about("List the automatic snapshots, oldest first"),
+        ])
+}
+
+fn subcommand_edit() -> Command {
+    Command::new("edit").about("Edit a Project in the database").args(&[
+        Arg::new("project").required(true).help("Name or id of the project to edit"),
+        Arg::new("name").short('n').long("name").help("New project name"),
+        Arg::new("desc").long("desc").help("New description of the project"),
+        Arg::new("directory")
+            .short('d')
+            .long("directory")
+            .help("New directory of the project")
+            .value_parser(value_parser!(PathBuf)),
+        Arg::new("tags")
+            .long("tag")
+            .num_args(1..)
+            .action(ArgAction::Append)
+            .help("Replace the tags of the project"),
+        Arg::new("language")
+            .short('l')
+            .long("language")
+            .help("Primary programming language used"),
         Arg::new("category")
             .short('c')
             .long("category")
@@ -70,11 +203,153 @@ fn subcommand_add() -> Command {
             .short('i')
             .long("interactive")
             .action(ArgAction::SetTrue),
+        Arg::new("refactor")
+            .long("refactor")
+            .help("Move the project directory to match the updated project")
+            .conflicts_with("directory")
+            .action(ArgAction::SetTrue),
     ])
 }
 
+fn subcommand_export() -> Command {
+    Command::new("export")
+        .about("Export the projects to a portable file that can be imported on another machine")
+        .args(&[
+            Arg::new("format")
+                .long("format")
+                .value_parser(["json", "toml"])
+                .help("Format of the export, defaults to the extension of the output file or JSON"),
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("File to write the export to, prints to stdout if nothing is provided")
+                .value_parser(value_parser!(PathBuf)),
+        ])
+}
+
+fn subcommand_import() -> Command {
+    Command::new("import")
+        .about("Import projects exported with `fpm export`, placing them relative to this machine's `base_dir`")
+        .args(&[
+            Arg::new("file")
+                .required(true)
+                .help("The file to import")
+                .value_parser(value_parser!(PathBuf)),
+            Arg::new("format")
+                .long("format")
+                .value_parser(["json", "toml"])
+                .help("Format of the file, defaults to its extension"),
+            Arg::new("strategy")
+                .long("strategy")
+                .default_value("skip")
+                .value_parser(["skip", "overwrite", "rename"])
+                .help("What to do when a project with the same name already exists"),
+            Arg::new("dry_run")
+                .long("dry-run")
+                .help("Show what would be imported without changing anything")
+                .action(ArgAction::SetTrue),
+        ])
+}
+
+fn subcommand_init() -> Command {
+    Command::new("init")
+        .about("Set up the config and database, this runs automatically the first time fpm is used")
+        .args(&[Arg::new("force")
+            .short('f')
+            .long("force")
+            .help("Replace an existing config without asking")
+            .action(ArgAction::SetTrue)])
+}
+
 fn subcommand_list() -> Command {
-    Command::new("list").about("List the projects in the database")
+    Command::new("list").about("List the projects in the database").args(&[
+        Arg::new("tags")
+            .long("tag")
+            .num_args(1..)
+            .action(ArgAction::Append)
+            .help("Only list projects that have all of these tags"),
+        Arg::new("language")
+            .short('l')
+            .long("language")
+            .help("Only list projects using this language"),
+        Arg::new("category")
+            .short('c')
+            .long("category")
+            .help("Only list projects in this category"),
+        Arg::new("name")
+            .short('n')
+            .long("name")
+            .help("Only list projects with a name matching this glob pattern, I.E. `fpm-*`"),
+        Arg::new("search")
+            .short('s')
+            .long("search")
+            .help("Only list projects with this text in their name or description"),
+        Arg::new("sort")
+            .long("sort")
+            .value_parser(["name", "created", "opened", "frecency"])
+            .help("Order of the projects, `frecency` puts the projects you open most often and most recently first"),
+        Arg::new("format")
+            .long("format")
+            .default_value("table")
+            .value_parser(value_parser!(Format))
+            .help("How to print the projects"),
+        Arg::new("columns")
+            .long("columns")
+            .value_delimiter(',')
+            .default_values(output::DEFAULT_COLUMNS)
+            .value_parser(value_parser!(Column))
+            .help("Comma separated list of the fields to show"),
+    ])
+}
+
+fn subcommand_move() -> Command {
+    Command::new("move")
+        .about("Move a Project's directory to the folder generated from its current details")
+        .args(&[
+            Arg::new("project").required(true).help("Name or id of the project to move"),
+            Arg::new("dry_run")
+                .long("dry-run")
+                .help("Show where the project would be moved without changing anything")
+                .action(ArgAction::SetTrue),
+        ])
+}
+
+fn subcommand_open() -> Command {
+    Command::new("open")
+        .about("Open a Project with the configured editor or command")
+        .args(&[Arg::new("project").required(true).help("Name or id of the project to open")])
+}
+
+fn subcommand_path() -> Command {
+    Command::new("path")
+        .about("Print the directory of the project best matching a fuzzy search")
+        .args(&[Arg::new("query")
+            .num_args(0..)
+            .help("Text to match against the project names, tags and descriptions")])
+}
+
+fn subcommand_remove() -> Command {
+    Command::new("remove").about("Remove a Project from the database").args(&[
+        Arg::new("project").required(true).help("Name or id of the project to remove"),
+        Arg::new("delete_files")
+            .long("delete-files")
+            .help("Also delete the project directory from disk")
+            .action(ArgAction::SetTrue),
+        Arg::new("dry_run")
+            .long("dry-run")
+            .help("Show what would be removed without changing anything")
+            .action(ArgAction::SetTrue),
+        Arg::new("outside_base_dir")
+            .long("outside-base-dir")
+            .help("Allow --delete-files to delete a directory that is not inside base_dir")
+            .requires("delete_files")
+            .action(ArgAction::SetTrue),
+        Arg::new("force")
+            .short('f')
+            .long("force")
+            .help("Bypass conformation prompt when deleting files")
+            .action(ArgAction::SetTrue),
+    ])
 }
 
 fn subcommand_reset() -> Command {
@@ -87,10 +362,129 @@ fn subcommand_reset() -> Command {
             .action(ArgAction::SetTrue)])
 }
 
+fn subcommand_scan() -> Command {
+    Command::new("scan")
+        .about("Find existing projects in a directory tree and add them to the database")
+        .args(&[
+            Arg::new("root")
+                .required(true)
+                .help("The directory to search for projects")
+                .value_parser(value_parser!(PathBuf)),
+            Arg::new("dry_run")
+                .long("dry-run")
+                .help("Show the projects that would be added without adding them")
+                .action(ArgAction::SetTrue),
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Bypass conformation prompt and add the projects")
+                .action(ArgAction::SetTrue),
+        ])
+}
+
+fn subcommand_shell_init() -> Command {
+    Command::new("shell-init")
+        .about("Print a shell function that changes directory to a project, I.E. `eval \"$(fpm shell-init bash)\"`")
+        .args(&[
+            Arg::new("shell")
+                .required(true)
+                .value_parser(["bash", "zsh", "fish"])
+                .help("The shell to generate the function for"),
+            Arg::new("cmd")
+                .long("cmd")
+                .default_value("fcd")
+                .help("Name of the generated function"),
+        ])
+}
+
+fn subcommand_status() -> Command {
+    Command::new("status")
+        .about("Show the git branch, changes, ahead/behind counts and stashes of every project")
+        .args(&[
+            Arg::new("dirty")
+                .long("dirty")
+                .help("Only show projects with uncommitted changes")
+                .action(ArgAction::SetTrue),
+            Arg::new("unpushed")
+                .long("unpushed")
+                .help("Only show projects with commits that are not on their upstream, or branches without one")
+                .action(ArgAction::SetTrue),
+            Arg::new("format")
+                .long("format")
+                .default_value("table")
+                .value_parser(value_parser!(Format))
+                .help("How to print the status"),
+        ])
+}
+
+fn subcommand_tag() -> Command {
+    let project_and_tags = [
+        Arg::new("project").required(true).help("Name or id of the project"),
+        Arg::new("tags").required(true).num_args(1..).help("The tags"),
+    ];
+
+    Command::new("tag")
+        .about("Manage the tags of the projects")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommands(vec![
+            Command::new("list")
+                .about("List every tag and how many projects have it")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .default_value("table")
+                        .value_parser(value_parser!(Format))
+                        .help("How to print the tags"),
+                ),
+            Command::new("rename").about("Rename a tag on every project").args(&[
+                Arg::new("old").required(true).help("The current name of the tag"),
+                Arg::new("new").required(true).help("The new name of the tag"),
+            ]),
+            Command::new("add").about("Add tags to a project").args(&project_and_tags),
+            Command::new("rm").about("Remove tags from a project").args(&project_and_tags),
+            Command::new("prune").about("Remove blank tags and tags of projects that no longer exist"),
+        ])
+}
+
+fn subcommand_verify() -> Command {
+    Command::new("verify")
+        .about("Check that every project directory still exists and offer to fix the missing ones")
+        .args(&[Arg::new("format")
+            .long("format")
+            .default_value("table")
+            .value_parser(value_parser!(Format))
+            .help("How to print the missing projects, any format but table only prints them without fixing anything")])
+}
+
+/// The commands a person runs by hand, only these start the first run wizard
+const WIZARD_COMMANDS: [&str; 8] = ["new", "add", "edit", "open", "move", "remove", "scan", "import"];
+
 pub fn parse() -> Result<()> {
     let matches = cli().get_matches();
 
-    let config = Config::load()?;
+    let sources = ConfigSources::discover(matches.get_one::<PathBuf>("config").cloned());
+    // These commands have to work before there is a valid config, `config` so it can be fixed
+    match matches.subcommand() {
+        Some(("config", sub_matches)) => return commands::config::config(sub_matches, &sources),
+        Some(("init", sub_matches)) => return commands::init::init(sub_matches, &sources),
+        Some(("shell-init", sub_matches)) => {
+            commands::shell_init::shell_init(sub_matches);
+            return Ok(());
+        },
+        _ => {},
+    }
+
+    // Set fpm up on the first run, unless the input comes from a script or the output is read by one,
+    // I.E. `fpm path` in `fcd`
+    let interactive = matches.subcommand_name().is_some_and(|name| WIZARD_COMMANDS.contains(&name));
+    if interactive && !sources.any_exist() && std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+        if let Some(path) = sources.target() {
+            eprintln!("No config was found, let's set fpm up");
+            commands::init::wizard(path)?;
+        }
+    }
+    let config = Config::load_with(&sources)?;
 
     match matches.subcommand() {
         Some(("new", sub_matches)) => {
@@ -99,12 +493,48 @@ pub fn parse() -> Result<()> {
         Some(("add", sub_matches)) => {
             commands::add::add(sub_matches, &config)?;
         },
-        Some(("list", _)) => {
-            commands::list::list(&config)?;
+        Some(("db", sub_matches)) => {
+            commands::db::db(sub_matches, &config)?;
+        },
+        Some(("edit", sub_matches)) => {
+            commands::edit::edit(sub_matches, &config)?;
+        },
+        Some(("export", sub_matches)) => {
+            commands::export::export(sub_matches, &config)?;
+        },
+        Some(("import", sub_matches)) => {
+            commands::import::import(sub_matches, &config)?;
+        },
+        Some(("list", sub_matches)) => {
+            commands::list::list(sub_matches, &config)?;
+        },
+        Some(("move", sub_matches)) => {
+            commands::r#move::r#move(sub_matches, &config)?;
+        },
+        Some(("open", sub_matches)) => {
+            commands::open::open(sub_matches, &config)?;
+        },
+        Some(("path", sub_matches)) => {
+            commands::path::path(sub_matches, &config)?;
+        },
+        Some(("remove", sub_matches)) => {
+            commands::remove::remove(sub_matches, &config)?;
         },
         Some(("reset", sub_matches)) => {
             commands::reset::reset(sub_matches, &config)?;
         },
+        Some(("scan", sub_matches)) => {
+            commands::scan::scan(sub_matches, &config)?;
+        },
+        Some(("status", sub_matches)) => {
+            commands::status::status(sub_matches, &config)?;
+        },
+        Some(("tag", sub_matches)) => {
+            commands::tag::tag(sub_matches, &config)?;
+        },
+        Some(("verify", sub_matches)) => {
+            commands::verify::verify(sub_matches, &config)?;
+        },
         Some((command, _)) => {
             println!("Code has not yet been written from `{command}`");
         },
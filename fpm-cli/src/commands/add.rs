@@ -1,12 +1,13 @@
+use crate::commands::open::open_and_record;
 use crate::utils::Result;
 use clap::ArgMatches;
 use console::Term;
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::Input;
-use fpm_lib::{config::Config, database::add_project, project::Project};
+use fpm_lib::{config::Config, detect::detect, project::Project, store::open_store, validate::validate_project};
 use std::path::PathBuf;
 
-struct AddParams {
+pub(crate) struct AddParams {
     pub(crate) name: Option<String>,
     pub(crate) dir: Option<PathBuf>,
     pub(crate) desc: Option<String>,
@@ -20,7 +21,10 @@ pub fn add(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
     let mut dir = sub_matches.get_one::<PathBuf>("directory").cloned();
     let mut desc = sub_matches.get_one::<String>("desc").cloned();
     let mut language = sub_matches.get_one::<String>("language").cloned();
-    let mut category = sub_matches.get_one::<String>("category").cloned();
+    let mut category = sub_matches
+        .get_one::<String>("category")
+        .cloned()
+        .or_else(|| config.default_category.clone());
     let mut tags = sub_matches
         .get_many::<String>("tags")
         .into_iter()
@@ -45,6 +49,11 @@ pub fn add(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
         println!("Tags: {tags:?}");
         println!("Language: {language:?}");
         println!("Category: {category:?}");
+    } else if let Some(dir) = &dir {
+        let detected = detect(dir);
+        name = name.or(detected.name);
+        desc = desc.or(detected.desc);
+        language = language.or(detected.language);
     }
     if name.is_none() {
         println!("A name is required for a project, please specify one");
@@ -56,12 +65,17 @@ pub fn add(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
     }
     let mut project = Project::new(name, desc, tags, language, category);
     project.directory = dir;
-    add_project(config, &project)?;
+    validate_project(&project)?;
+    let mut store = open_store(config)?;
+    project.rowid = Some(store.add(&project)?);
+    if sub_matches.get_flag("open") {
+        open_and_record(config, store.as_mut(), &mut project)?;
+    }
     println!("{project:#?}");
     Ok(())
 }
 
-fn add_params_interactive(
+pub(crate) fn add_params_interactive(
     name: Option<String>,
     dir: Option<PathBuf>,
     desc: Option<String>,
@@ -69,21 +83,27 @@ fn add_params_interactive(
     language: Option<String>,
     category: Option<String>,
 ) -> Result<AddParams> {
-    // Get Name
-    let name = Some(
-        Input::with_theme(&ColorfulTheme::default())
-            .with_prompt("Project Name")
-            .with_initial_text(name.unwrap_or_default())
-            .interact_text()?,
-    );
-
     // Get Directory
     let mut dir_str = dir.unwrap_or_default().to_str().unwrap_or_default().to_owned();
     dir_str = Input::with_theme(&ColorfulTheme::default())
         .with_prompt("Project Directory")
         .with_initial_text(dir_str)
         .interact_text()?;
-    let dir = Some(PathBuf::from(dir_str));
+    let dir = PathBuf::from(dir_str);
+
+    // Offer what can be read from the directory as defaults
+    let detected = detect(&dir);
+    let dir = Some(dir);
+    let desc = desc.or(detected.desc);
+    let language = language.or(detected.language);
+
+    // Get Name
+    let name = Some(
+        Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Project Name")
+            .with_initial_text(name.or(detected.name).unwrap_or_default())
+            .interact_text()?,
+    );
 
     // Get Description
     let mut desc: Option<String> = Some(
@@ -99,7 +119,7 @@ fn add_params_interactive(
         if d.is_empty() {
             desc = None;
         }
-    };
+    }
 
     // Get Tags
     let term = Term::stdout();
@@ -130,7 +150,7 @@ fn add_params_interactive(
         if lang.is_empty() {
             language = None;
         }
-    };
+    }
 
     // Get Category
     let mut category: Option<String> = Some(
@@ -146,7 +166,7 @@ fn add_params_interactive(
         if d.is_empty() {
             category = None;
         }
-    };
+    }
 
     Ok(AddParams {
         name,
@@ -0,0 +1,115 @@
+use crate::utils::{Error, Result};
+use clap::ArgMatches;
+use fpm_lib::{
+    config::{Config, ConfigSources},
+    error::Error as FpmError,
+    open::open_args,
+    settings::{get_value, validate_config, ConfigFile, ConfigProblem},
+};
+use std::path::Path;
+use std::process::Command;
+
+#[cfg(windows)]
+const DEFAULT_EDITOR: &str = "notepad";
+#[cfg(not(windows))]
+const DEFAULT_EDITOR: &str = "vi";
+
+pub fn config(sub_matches: &ArgMatches, sources: &ConfigSources) -> Result<()> {
+    let key = || {
+        sub_matches
+            .subcommand()
+            .and_then(|(_, m)| m.get_one::<String>("key"))
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    match sub_matches.subcommand() {
+        Some(("get", _)) => {
+            let key = key();
+            let config = Config::load_with(sources)?;
+            match get_value(&config, &key)? {
+                Some(toml::Value::String(value)) => println!("{value}"),
+                Some(value) => println!("{value}"),
+                None => return Err(Error::NotSet(key)),
+            }
+        },
+        Some(("set", set_matches)) => {
+            let value = set_matches.get_one::<String>("value").cloned().unwrap_or_default();
+            let mut file = ConfigFile::open(target(sources)?)?;
+            file.set(&key(), &value)?;
+            file.save()?;
+        },
+        Some(("unset", _)) => {
+            let key = key();
+            let mut file = ConfigFile::open(target(sources)?)?;
+            if file.unset(&key)? {
+                file.save()?;
+            } else {
+                println!("`{key}` is not set in {}", file.path().display());
+            }
+        },
+        Some(("list", _)) => {
+            let config = Config::load_with(sources)?;
+            print!("{}", toml::to_string(&config)?);
+        },
+        Some(("path", _)) => {
+            println!("{}", target(sources)?.display());
+        },
+        Some(("edit", _)) => {
+            edit(target(sources)?)?;
+            report(sources)?;
+        },
+        Some(("validate", _)) => {
+            report(sources)?;
+        },
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
+
+fn target(sources: &ConfigSources) -> Result<&Path> {
+    sources
+        .target()
+        .ok_or(Error::Fpm(FpmError::Static("no config file to change")))
+}
+
+/// Open `path` in the editor from `VISUAL` or `EDITOR` and wait for it to close
+fn edit(path: &Path) -> Result<()> {
+    if !path.exists() {
+        ConfigFile::open(path)?.save()?;
+    }
+
+    let command = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|command| !command.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_owned());
+    let args = open_args(&command, path);
+    let Some((program, args)) = args.split_first() else {
+        return Err(Error::Fpm(FpmError::ConfigMissingValue("EDITOR".to_owned())));
+    };
+
+    let status = Command::new(program).args(args).status()?;
+    if !status.success() {
+        return Err(Error::Fpm(FpmError::CommandFailed(command, status.to_string())));
+    }
+    Ok(())
+}
+
+/// Print every problem with the config, failing if there are any
+fn report(sources: &ConfigSources) -> Result<()> {
+    let (warnings, errors): (Vec<_>, Vec<_>) = validate_config(sources).into_iter().partition(ConfigProblem::is_warning);
+    for warning in &warnings {
+        eprintln!("warning: {warning}");
+    }
+    if errors.is_empty() {
+        println!("The config is valid");
+        return Ok(());
+    }
+
+    for error in &errors {
+        eprintln!("- {error}");
+    }
+    Err(Error::InvalidConfig(errors.len()))
+}
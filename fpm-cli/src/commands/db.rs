@@ -0,0 +1,45 @@
+use crate::utils::Result;
+use clap::ArgMatches;
+use dialoguer::Confirm;
+use fpm_lib::{
+    backup::{backup_database, default_backup_path, list_snapshots, restore_database},
+    config::Config,
+};
+use std::path::PathBuf;
+
+pub fn db(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
+    match sub_matches.subcommand() {
+        Some(("backup", sub_matches)) => {
+            let path = match sub_matches.get_one::<PathBuf>("path") {
+                Some(path) => path.clone(),
+                None => default_backup_path(config),
+            };
+            backup_database(config, &path)?;
+            println!("Database backed up to {}", path.display());
+        },
+        Some(("restore", sub_matches)) => {
+            let path = sub_matches.get_one::<PathBuf>("path").cloned().unwrap_or_default();
+            if sub_matches.get_flag("force")
+                || Confirm::new()
+                    .with_prompt(format!(
+                        "Are you sure you want to replace the database with {}? A snapshot is saved first",
+                        path.display()
+                    ))
+                    .interact()?
+            {
+                if let Some(snapshot) = restore_database(config, &path)? {
+                    println!("The previous database was saved to {}", snapshot.display());
+                }
+                println!("Database restored from {}", path.display());
+            }
+        },
+        Some(("snapshots", _)) => {
+            for snapshot in list_snapshots(config)? {
+                println!("{}", snapshot.display());
+            }
+        },
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
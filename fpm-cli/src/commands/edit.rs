@@ -0,0 +1,65 @@
+use crate::commands::add::add_params_interactive;
+use crate::utils::{create_spinner, Result};
+use clap::ArgMatches;
+use fpm_lib::{config::Config, store::open_store, validate::validate_project};
+use std::path::PathBuf;
+
+pub fn edit(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
+    let key = sub_matches.get_one::<String>("project").cloned().unwrap_or_default();
+    let mut store = open_store(config)?;
+    let mut project = store.find_project(&key)?;
+
+    if let Some(name) = sub_matches.get_one::<String>("name") {
+        project.name = Some(name.clone());
+    }
+    if let Some(dir) = sub_matches.get_one::<PathBuf>("directory") {
+        project.directory = Some(dir.clone());
+    }
+    if let Some(desc) = sub_matches.get_one::<String>("desc") {
+        project.desc = Some(desc.clone());
+    }
+    if let Some(language) = sub_matches.get_one::<String>("language") {
+        project.language = Some(language.clone());
+    }
+    if let Some(category) = sub_matches.get_one::<String>("category") {
+        project.category = Some(category.clone());
+    }
+    if let Some(tags) = sub_matches.get_many::<String>("tags") {
+        project.tags = Some(tags.cloned().collect());
+    }
+
+    let interactive = sub_matches.get_flag("interactive");
+    if interactive {
+        let new_params = add_params_interactive(
+            project.name.clone(),
+            project.directory.clone(),
+            project.desc.clone(),
+            project.tags.clone().unwrap_or_default(),
+            project.language.clone(),
+            project.category.clone(),
+        )?;
+
+        project.name = new_params.name;
+        project.directory = new_params.dir;
+        project.desc = new_params.desc;
+        project.tags = Some(new_params.tags);
+        project.language = new_params.language;
+        project.category = new_params.category;
+    }
+
+    if project.name.as_deref().unwrap_or_default().is_empty() {
+        println!("A name is required for a project, please specify one");
+        return Ok(());
+    }
+    validate_project(&project)?;
+
+    if sub_matches.get_flag("refactor") {
+        let pb = create_spinner("Moving Folder...")?;
+        let dir = store.move_project(config, &mut project)?;
+        pb.finish_with_message(format!("Folder moved to `{}`", dir.display()));
+    } else {
+        store.update(&project)?;
+    }
+    println!("{project:#?}");
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use crate::utils::Result;
+use clap::ArgMatches;
+use fpm_lib::{
+    config::Config,
+    registry::{export_registry, RegistryFormat},
+    store::open_store,
+};
+use fs_err as fs;
+use std::path::PathBuf;
+
+pub fn export(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
+    let output = sub_matches.get_one::<PathBuf>("output");
+    let format = match sub_matches.get_one::<String>("format").map(String::as_str) {
+        Some("toml") => RegistryFormat::Toml,
+        Some(_) => RegistryFormat::Json,
+        None => output.map_or(RegistryFormat::Json, |path| RegistryFormat::from_path(path)),
+    };
+
+    let registry = export_registry(config, open_store(config)?.as_ref())?;
+    let content = registry.to_string(format)?;
+    match output {
+        Some(path) => {
+            fs::write(path, content)?;
+            println!("Exported {} projects to {}", registry.projects.len(), path.display());
+        },
+        None => println!("{content}"),
+    }
+
+    Ok(())
+}
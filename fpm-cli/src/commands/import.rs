@@ -0,0 +1,62 @@
+use crate::utils::Result;
+use clap::ArgMatches;
+use fpm_lib::{
+    backup::snapshot,
+    config::Config,
+    registry::{apply_import, plan_import, MergeStrategy, Registry, RegistryFormat},
+    store::open_store,
+};
+use fs_err as fs;
+use std::path::PathBuf;
+
+pub fn import(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
+    let file = sub_matches.get_one::<PathBuf>("file").cloned().unwrap_or_default();
+    let dry_run = sub_matches.get_flag("dry_run");
+    let strategy = match sub_matches.get_one::<String>("strategy").map(String::as_str) {
+        Some("overwrite") => MergeStrategy::Overwrite,
+        Some("rename") => MergeStrategy::Rename,
+        _ => MergeStrategy::Skip,
+    };
+    let format = match sub_matches.get_one::<String>("format").map(String::as_str) {
+        Some("toml") => RegistryFormat::Toml,
+        Some(_) => RegistryFormat::Json,
+        None => RegistryFormat::from_path(&file),
+    };
+
+    let registry = Registry::parse(&fs::read_to_string(&file)?, format)?;
+    let mut store = open_store(config)?;
+    let plan = plan_import(config, &registry, &store.load_projects()?, strategy)?;
+
+    for name in &plan.report.added {
+        println!("Add `{name}`");
+    }
+    for name in &plan.report.overwritten {
+        println!("Overwrite `{name}`");
+    }
+    for (name, new_name) in &plan.report.renamed {
+        println!("Add `{name}` as `{new_name}`");
+    }
+    for name in &plan.report.skipped {
+        println!("Skip `{name}`, a project with that name already exists");
+    }
+
+    if dry_run {
+        println!("Nothing was imported");
+        return Ok(());
+    }
+
+    // Overwritten projects can not be brought back, so keep a copy of the database first
+    if !plan.update.is_empty() {
+        if let Some(snapshot) = snapshot(config)? {
+            println!("The database was saved to {} before overwriting projects", snapshot.display());
+        }
+    }
+    apply_import(store.as_mut(), &plan)?;
+    println!(
+        "Imported {} projects, skipped {}",
+        plan.add.len() + plan.update.len(),
+        plan.report.skipped.len()
+    );
+
+    Ok(())
+}
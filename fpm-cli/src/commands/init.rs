@@ -0,0 +1,136 @@
+use crate::commands::scan::scan_root;
+use crate::utils::{Error, Result};
+use clap::ArgMatches;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, Input};
+use fpm_lib::{
+    config::{Config, ConfigSources},
+    error::Error as FpmError,
+    settings::ConfigFile,
+    store::open_store,
+};
+use fs_err as fs;
+use std::path::{Path, PathBuf};
+
+pub fn init(sub_matches: &ArgMatches, sources: &ConfigSources) -> Result<()> {
+    let Some(path) = sources.target() else {
+        return Err(Error::Fpm(FpmError::Static("no config file to write")));
+    };
+
+    if path.exists()
+        && !sub_matches.get_flag("force")
+        && !Confirm::new()
+            .with_prompt(format!(
+                "`{}` already exists, do you want to change its settings?",
+                path.display()
+            ))
+            .default(false)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    wizard(path)
+}
+
+/// Ask for the main settings, write them to `path` and create the database.
+///
+/// The prompts and messages go to stderr, so they never end up in the output of the command that started the wizard.
+pub fn wizard(path: &Path) -> Result<()> {
+    let theme = ColorfulTheme::default();
+    // The answers are filled in from the current settings, the rest of the file and its comments are kept
+    let config = match fs::read_to_string(path) {
+        Ok(content) => match Config::parse(&content) {
+            Ok(config) => config,
+            Err(e) => {
+                // Start over from an empty file, but keep the settings that could not be read
+                let mut backup = path.as_os_str().to_owned();
+                backup.push(".bak");
+                fs::rename(path, &backup)?;
+                eprintln!(
+                    "`{}` could not be read ({e}), it was moved to `{}`",
+                    path.display(),
+                    Path::new(&backup).display()
+                );
+                Config::new()
+            },
+        },
+        Err(_) => Config::new(),
+    };
+    let mut file = ConfigFile::open(path)?;
+
+    let base_dir = config
+        .base_dir
+        .clone()
+        .or_else(|| Config::default_base_dir().map(|dir| dir.display().to_string()))
+        .unwrap_or_default();
+    let base_dir: String = Input::with_theme(&theme)
+        .with_prompt("Folder to create new projects in")
+        .with_initial_text(base_dir)
+        .interact_text()?;
+    if !Path::new(&base_dir).is_dir()
+        && Confirm::with_theme(&theme)
+            .with_prompt(format!("`{base_dir}` does not exist, create it?"))
+            .default(true)
+            .interact()?
+    {
+        fs::create_dir_all(&base_dir)?;
+    }
+    file.set("base_dir", &base_dir)?;
+
+    let database_path: String = Input::with_theme(&theme)
+        .with_prompt("Database location")
+        .with_initial_text(config.database_path.clone())
+        .interact_text()?;
+    file.set("database_path", &database_path)?;
+
+    let editor = config
+        .editor
+        .clone()
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .unwrap_or_default();
+    let editor: String = Input::with_theme(&theme)
+        .with_prompt("Command to open projects with, `{dir}` is replaced with the project folder")
+        .with_initial_text(editor)
+        .allow_empty(true)
+        .interact_text()?;
+    set_or_unset(&mut file, "editor", &editor)?;
+
+    let default_category: String = Input::with_theme(&theme)
+        .with_prompt("Default category for new projects (leave empty for none)")
+        .with_initial_text(config.default_category.clone().unwrap_or_default())
+        .allow_empty(true)
+        .interact_text()?;
+    set_or_unset(&mut file, "default_category", &default_category)?;
+
+    file.save()?;
+    eprintln!("Config written to {}", path.display());
+
+    // Opening the store creates the database
+    let config = Config::parse(&fs::read_to_string(path)?)?;
+    open_store(&config)?;
+    eprintln!("Database created at {}", config.database_path);
+
+    let base_dir = PathBuf::from(base_dir);
+    if base_dir.is_dir()
+        && Confirm::with_theme(&theme)
+            .with_prompt(format!("Scan `{}` for existing projects to add?", base_dir.display()))
+            .default(true)
+            .interact()?
+    {
+        scan_root(&base_dir, &config, false, false)?;
+    }
+
+    Ok(())
+}
+
+/// Set `key` to `value`, or remove it from the file when `value` is blank
+fn set_or_unset(file: &mut ConfigFile, key: &str, value: &str) -> Result<()> {
+    if value.trim().is_empty() {
+        file.unset(key)?;
+    } else {
+        file.set(key, value)?;
+    }
+    Ok(())
+}
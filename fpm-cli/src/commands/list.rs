@@ -1,19 +1,42 @@
+use crate::output::{print_projects, Column, Format};
 use crate::utils::Result;
-use fpm_lib::{config::Config, database::load_projects};
-use prettytable::{format, row, Table};
+use clap::ArgMatches;
+use fpm_lib::{
+    config::Config,
+    search::sort_by_frecency,
+    store::{open_store, ProjectFilter},
+};
 
-pub fn list(config: &Config) -> Result<()> {
-    let projects = load_projects(config)?;
-    let mut table = Table::new();
-    table.set_format(*format::consts::FORMAT_BOX_CHARS);
-    table.set_titles(row!["Name", "Description", "Directory"]);
-    for project in projects {
-        table.add_row(row![
-            project.name.unwrap_or_default(),
-            project.desc.unwrap_or_default(),
-            project.directory.unwrap_or_default().display()
-        ]);
+pub fn list(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
+    let filter = ProjectFilter {
+        tags: sub_matches
+            .get_many::<String>("tags")
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect(),
+        language: sub_matches.get_one::<String>("language").cloned(),
+        category: sub_matches.get_one::<String>("category").cloned(),
+        name: sub_matches.get_one::<String>("name").cloned(),
+        search: sub_matches.get_one::<String>("search").cloned(),
+    };
+
+    let format = sub_matches.get_one::<Format>("format").copied().unwrap_or(Format::Table);
+    let columns = sub_matches
+        .get_many::<Column>("columns")
+        .into_iter()
+        .flatten()
+        .copied()
+        .collect::<Vec<_>>();
+
+    let mut projects = open_store(config)?.query(&filter)?;
+    match sub_matches.get_one::<String>("sort").map(String::as_str) {
+        Some("name") => projects.sort_by(|a, b| a.name.cmp(&b.name)),
+        Some("created") => projects.sort_by_key(|p| std::cmp::Reverse(p.created_at)),
+        Some("opened") => projects.sort_by_key(|p| std::cmp::Reverse(p.last_opened_at)),
+        Some("frecency") => sort_by_frecency(&mut projects),
+        _ => {},
     }
-    table.printstd();
+    print_projects(&projects, format, &columns)?;
     Ok(())
 }
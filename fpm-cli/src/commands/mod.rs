@@ -1,4 +1,19 @@
 pub mod add;
+pub mod config;
+pub mod db;
+pub mod edit;
+pub mod export;
+pub mod import;
+pub mod init;
 pub mod list;
+pub mod r#move;
 pub mod new;
+pub mod open;
+pub mod path;
+pub mod remove;
 pub mod reset;
+pub mod scan;
+pub mod shell_init;
+pub mod status;
+pub mod tag;
+pub mod verify;
@@ -0,0 +1,29 @@
+use crate::utils::{create_spinner, Result};
+use clap::ArgMatches;
+use fpm_lib::{config::Config, store::open_store};
+
+pub fn r#move(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
+    let key = sub_matches.get_one::<String>("project").cloned().unwrap_or_default();
+    let dry_run = sub_matches.get_flag("dry_run");
+
+    let mut store = open_store(config)?;
+    let mut project = store.find_project(&key)?;
+    let current = project.directory.clone().unwrap_or_default();
+    let target = config.gen_project_folder(&project)?;
+
+    if current == target {
+        println!("`{}` is already in the right place", current.display());
+        return Ok(());
+    }
+
+    if dry_run {
+        println!("Would move `{}` to `{}`", current.display(), target.display());
+        return Ok(());
+    }
+
+    let pb = create_spinner("Moving Folder...")?;
+    let target = store.move_project(config, &mut project)?;
+    pb.finish_with_message(format!("Moved `{}` to `{}`", current.display(), target.display()));
+
+    Ok(())
+}
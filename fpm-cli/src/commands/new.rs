@@ -1,9 +1,10 @@
+use crate::commands::open::open_and_record;
 use crate::utils::{create_spinner, Error, Result};
 use clap::ArgMatches;
 use console::Term;
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::Input;
-use fpm_lib::{config::Config, database::add_project, project::Project};
+use fpm_lib::{config::Config, git::repo_name_from_url, project::Project, store::open_store, template::resolve_templates};
 use std::path::PathBuf;
 
 struct NewParams {
@@ -19,13 +20,27 @@ pub fn new(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
     let mut name = sub_matches.get_one::<String>("name").cloned();
     let mut desc = sub_matches.get_one::<String>("desc").cloned();
     let mut language = sub_matches.get_one::<String>("language").cloned();
-    let mut category = sub_matches.get_one::<String>("category").cloned();
+    let mut category = sub_matches
+        .get_one::<String>("category")
+        .cloned()
+        .or_else(|| config.default_category.clone());
     let mut tags = sub_matches
         .get_many::<String>("tags")
         .into_iter()
         .flatten()
         .cloned()
         .collect::<Vec<_>>();
+    let template_names = sub_matches
+        .get_many::<String>("templates")
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>();
+    let templates = resolve_templates(config, &template_names)?;
+    let git_url = sub_matches.get_one::<String>("git").cloned();
+    if name.is_none() {
+        name = git_url.as_deref().and_then(repo_name_from_url);
+    }
     let interactive = sub_matches.get_flag("interactive");
     if interactive {
         let new_params = new_params_interactive(name, desc, tags, language, category)?;
@@ -48,9 +63,19 @@ pub fn new(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
         return Ok(());
     }
     let mut project = Project::new(name, desc, tags, language, category);
-    let pb = create_spinner("Creating Folder...")?;
-    match project.build(dir, config) {
-        Ok(_) => {},
+    let result = if let Some(url) = &git_url {
+        let pb = create_spinner("Cloning Repository...")?;
+        let result = project.build_from_git(url, dir, config);
+        pb.finish_and_clear();
+        result
+    } else {
+        let pb = create_spinner("Creating Folder...")?;
+        let result = project.build(dir, &templates, config);
+        pb.finish_and_clear();
+        result
+    };
+    match result {
+        Ok(()) => {},
         Err(e) => match e {
             fpm_lib::error::Error::ConfigMissingValue(e) => {
                 println!(
@@ -60,9 +85,20 @@ pub fn new(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
             },
             e => return Err(Error::Fpm(e)),
         },
-    };
-    pb.finish_with_message("Folder Created");
-    add_project(config, &project)?;
+    }
+    println!(
+        "{}",
+        if git_url.is_some() {
+            "Repository Cloned"
+        } else {
+            "Folder Created"
+        }
+    );
+    let mut store = open_store(config)?;
+    project.rowid = Some(store.add(&project)?);
+    if sub_matches.get_flag("open") {
+        open_and_record(config, store.as_mut(), &mut project)?;
+    }
     println!("{project:#?}");
     Ok(())
 }
@@ -96,7 +132,7 @@ fn new_params_interactive(
         if d.is_empty() {
             desc = None;
         }
-    };
+    }
 
     // Get Tags
     let term = Term::stdout();
@@ -127,7 +163,7 @@ fn new_params_interactive(
         if lang.is_empty() {
             language = None;
         }
-    };
+    }
 
     // Get Category
     let mut category: Option<String> = Some(
@@ -143,7 +179,7 @@ fn new_params_interactive(
         if d.is_empty() {
             category = None;
         }
-    };
+    }
 
     Ok(NewParams {
         name,
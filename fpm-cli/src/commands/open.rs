@@ -0,0 +1,23 @@
+use crate::utils::Result;
+use clap::ArgMatches;
+use fpm_lib::{
+    config::Config,
+    open::open_project,
+    project::Project,
+    store::{open_store, ProjectStore},
+};
+
+pub fn open(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
+    let key = sub_matches.get_one::<String>("project").cloned().unwrap_or_default();
+
+    let mut store = open_store(config)?;
+    let mut project = store.find_project(&key)?;
+    open_and_record(config, store.as_mut(), &mut project)
+}
+
+/// Open `project` with its configured command and record the usage for frecency
+pub(crate) fn open_and_record(config: &Config, store: &mut dyn ProjectStore, project: &mut Project) -> Result<()> {
+    open_project(config, project)?;
+    store.record_open(project)?;
+    Ok(())
+}
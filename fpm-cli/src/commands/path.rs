@@ -0,0 +1,76 @@
+use crate::utils::{Error, Result};
+use clap::ArgMatches;
+use console::Term;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::FuzzySelect;
+use fpm_lib::{
+    config::Config,
+    project::Project,
+    search::{best_match, fuzzy_rank, sort_by_frecency},
+    store::open_store,
+};
+
+pub fn path(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
+    let query = sub_matches
+        .get_many::<String>("query")
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut store = open_store(config)?;
+    let mut projects = store.load_projects()?;
+    sort_by_frecency(&mut projects);
+    let matches = fuzzy_rank(&projects, &query);
+
+    let project = if let Some(project) = best_match(&matches, &query) {
+        Some(project)
+    } else if matches.is_empty() && !query.is_empty() {
+        None
+    } else {
+        let candidates = if query.is_empty() {
+            projects.iter().collect::<Vec<_>>()
+        } else {
+            matches.iter().map(|m| m.project).collect()
+        };
+        pick(&candidates, &query)?
+    };
+
+    let Some(mut project) = project.filter(|p| p.directory.is_some()).cloned() else {
+        return Err(Error::Fpm(fpm_lib::error::Error::ProjectNotFound(query)));
+    };
+
+    store.record_open(&mut project)?;
+    println!("{}", project.directory.unwrap_or_default().display());
+
+    Ok(())
+}
+
+/// Let the user choose between `candidates`, the prompt is drawn on stderr so stdout only ever holds the path
+fn pick<'a>(candidates: &[&'a Project], query: &str) -> Result<Option<&'a Project>> {
+    let term = Term::stderr();
+    if !term.features().is_attended() {
+        return Ok(candidates.first().copied());
+    }
+
+    let items = candidates
+        .iter()
+        .map(|p| {
+            format!(
+                "{} - {}",
+                p.name.as_deref().unwrap_or_default(),
+                p.directory.clone().unwrap_or_default().display()
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Project")
+        .with_initial_text(query)
+        .items(&items)
+        .default(0)
+        .interact_on_opt(&term)?;
+
+    Ok(selection.map(|i| candidates[i]))
+}
@@ -0,0 +1,74 @@
+use crate::utils::Result;
+use clap::ArgMatches;
+use dialoguer::Confirm;
+use fpm_lib::{config::Config, error::Error, store::open_store, validate::ensure_within};
+use fs_err as fs;
+use std::path::Path;
+
+pub fn remove(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
+    let key = sub_matches.get_one::<String>("project").cloned().unwrap_or_default();
+    let delete_files = sub_matches.get_flag("delete_files");
+    let outside_base_dir = sub_matches.get_flag("outside_base_dir");
+    let dry_run = sub_matches.get_flag("dry_run");
+    let force = sub_matches.get_flag("force");
+
+    let mut store = open_store(config)?;
+    let project = store.find_project(&key)?;
+    let name = project.name.clone().unwrap_or_default();
+    let dir = project.directory.clone().filter(|dir| dir.exists());
+
+    if delete_files {
+        // Deleting files is irreversible, so the key has to name exactly one project
+        let rowid = key.parse::<i64>().ok();
+        let matches = store
+            .load_projects()?
+            .iter()
+            .filter(|p| p.name.as_deref() == Some(key.as_str()) || (rowid.is_some() && p.rowid == rowid))
+            .count();
+        if matches > 1 {
+            return Err(Error::AmbiguousProject(key).into());
+        }
+
+        if let (Some(dir), false) = (&dir, outside_base_dir) {
+            let Some(base_dir) = &config.base_dir else {
+                return Err(Error::ConfigMissingValue("base_dir".to_owned()).into());
+            };
+            ensure_within(Path::new(base_dir), dir)?;
+        }
+    }
+
+    if dry_run {
+        println!("Would remove `{name}` from the database");
+        if delete_files {
+            match &dir {
+                Some(dir) => println!("Would delete `{}` and everything in it", dir.display()),
+                None => println!("No directory on disk to delete"),
+            }
+        }
+        return Ok(());
+    }
+
+    if let (true, Some(dir)) = (delete_files, &dir) {
+        if !force
+            && !Confirm::new()
+                .with_prompt(format!(
+                    "Are you sure you want to delete `{}` and everything in it? This is irreversible",
+                    dir.display()
+                ))
+                .interact()?
+        {
+            return Ok(());
+        }
+    }
+
+    // Forget the project before its files are gone, a failed delete then only leaves a stray folder
+    store.remove(&project)?;
+    println!("Removed `{name}` from the database");
+
+    if let (true, Some(dir)) = (delete_files, &dir) {
+        fs::remove_dir_all(dir)?;
+        println!("Deleted `{}`", dir.display());
+    }
+
+    Ok(())
+}
@@ -8,10 +8,12 @@ pub fn reset(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
 
     if force
         || Confirm::new()
-            .with_prompt("Are you sure you want to reset the entire database? This is irreversible")
+            .with_prompt("Are you sure you want to reset the entire database? A snapshot is saved first")
             .interact()?
     {
-        database::reset_database(config)?;
+        if let Some(snapshot) = database::reset_database(config)? {
+            println!("Database reset, the previous database was saved to {}", snapshot.display());
+        }
     }
 
     Ok(())
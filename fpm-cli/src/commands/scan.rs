@@ -0,0 +1,62 @@
+use crate::utils::{create_spinner, Result};
+use clap::ArgMatches;
+use dialoguer::Confirm;
+use fpm_lib::{config::Config, scan::scan as scan_dir, store::open_store};
+use prettytable::{format, row, Table};
+use std::path::{Path, PathBuf};
+
+pub fn scan(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
+    let root = sub_matches.get_one::<PathBuf>("root").cloned().unwrap_or_default();
+    let dry_run = sub_matches.get_flag("dry_run");
+    let force = sub_matches.get_flag("force");
+
+    scan_root(&root, config, dry_run, force)
+}
+
+/// Find the new projects below `root`, show them and add them after asking for confirmation
+pub(crate) fn scan_root(root: &Path, config: &Config, dry_run: bool, force: bool) -> Result<()> {
+    let mut store = open_store(config)?;
+    let existing = store.load_projects()?;
+    let pb = create_spinner("Scanning for projects...")?;
+    let scanned = scan_dir(root, config, &existing)?;
+    pb.finish_and_clear();
+
+    for (dir, err) in &scanned.invalid {
+        eprintln!("Skipping `{}`: {err}", dir.display());
+    }
+    let projects = scanned.projects;
+
+    if projects.is_empty() {
+        println!("No new projects found in `{}`", root.display());
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    table.set_titles(row!["Name", "Category", "Language", "Directory"]);
+    for project in &projects {
+        table.add_row(row![
+            project.name.clone().unwrap_or_default(),
+            project.category.clone().unwrap_or_default(),
+            project.language.clone().unwrap_or_default(),
+            project.directory.clone().unwrap_or_default().display()
+        ]);
+    }
+    table.printstd();
+
+    if dry_run {
+        println!("Found {} new projects, nothing was added", projects.len());
+        return Ok(());
+    }
+
+    if force
+        || Confirm::new()
+            .with_prompt(format!("Add these {} projects to the database?", projects.len()))
+            .interact()?
+    {
+        store.add_projects(&projects)?;
+        println!("Added {} projects", projects.len());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use clap::ArgMatches;
+
+pub fn shell_init(sub_matches: &ArgMatches) {
+    let shell = sub_matches.get_one::<String>("shell").map_or("bash", String::as_str);
+    let cmd = sub_matches.get_one::<String>("cmd").map_or("fcd", String::as_str);
+
+    let script = match shell {
+        "fish" => format!("function {cmd}\n    set -l dir (command fpm path $argv); and cd $dir\nend\n"),
+        _ => format!("{cmd}() {{\n    local dir\n    dir=\"$(command fpm path \"$@\")\" && cd \"$dir\"\n}}\n"),
+    };
+    print!("{script}");
+}
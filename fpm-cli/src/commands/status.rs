@@ -0,0 +1,116 @@
+use crate::output::{print_records, Format, Value};
+use crate::utils::{create_spinner, Result};
+use clap::ArgMatches;
+use fpm_lib::{
+    config::Config,
+    status::{project_statuses, ProjectStatus},
+    store::open_store,
+};
+use prettytable::{format, row, Table};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn status(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
+    let dirty = sub_matches.get_flag("dirty");
+    let unpushed = sub_matches.get_flag("unpushed");
+    let format = sub_matches.get_one::<Format>("format").copied().unwrap_or(Format::Table);
+
+    let store = open_store(config)?;
+    let projects = store.load_projects()?;
+    let pb = create_spinner("Reading repositories...")?;
+    let mut statuses = project_statuses(&projects);
+    pb.finish_and_clear();
+
+    if dirty || unpushed {
+        statuses.retain(|project| {
+            project
+                .status
+                .as_ref()
+                .is_some_and(|status| (!dirty || status.dirty) && (!unpushed || status.is_unpushed()))
+        });
+    }
+
+    if format != Format::Table {
+        return print_statuses(&statuses, format);
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX))
+        .unwrap_or_default();
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    table.set_titles(row!["Name", "Branch", "State", "Ahead", "Behind", "Stashes", "Last Commit"]);
+    for project in &statuses {
+        let name = project.name.clone().unwrap_or_default();
+        match &project.status {
+            Some(status) => table.add_row(row![
+                name,
+                status.branch.as_deref().unwrap_or("(detached)"),
+                if status.dirty { "dirty" } else { "clean" },
+                r -> if status.upstream.is_some() { status.ahead.to_string() } else { "-".to_owned() },
+                r -> if status.upstream.is_some() { status.behind.to_string() } else { "-".to_owned() },
+                r -> status.stashes,
+                status.last_commit.map(|time| age(now, time)).unwrap_or_default()
+            ]),
+            None => table.add_row(row![name, "", project.error.as_deref().unwrap_or_default(), "", "", "", ""]),
+        };
+    }
+    table.printstd();
+
+    Ok(())
+}
+
+/// Print the statuses with their raw values, so the counts and timestamps can be read by other programs
+fn print_statuses(statuses: &[ProjectStatus], format: Format) -> Result<()> {
+    let count = |count: usize| Value::Int(i64::try_from(count).ok());
+    let keys = [
+        "id",
+        "name",
+        "directory",
+        "branch",
+        "dirty",
+        "upstream",
+        "ahead",
+        "behind",
+        "stashes",
+        "last_commit",
+        "error",
+    ];
+    let records = statuses
+        .iter()
+        .map(|project| {
+            let status = project.status.as_ref();
+            vec![
+                Value::Int(project.rowid),
+                Value::Text(project.name.clone()),
+                Value::Text(project.directory.as_ref().map(|dir| dir.display().to_string())),
+                Value::Text(status.and_then(|status| status.branch.clone())),
+                Value::Bool(status.map(|status| status.dirty)),
+                Value::Text(status.and_then(|status| status.upstream.clone())),
+                status.map_or(Value::Int(None), |status| count(status.ahead)),
+                status.map_or(Value::Int(None), |status| count(status.behind)),
+                status.map_or(Value::Int(None), |status| count(status.stashes)),
+                Value::Int(status.and_then(|status| status.last_commit)),
+                Value::Text(project.error.clone()),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    print_records(format, &keys, &keys, &records)
+}
+
+/// How long ago `time` was, I.E. `3 days ago`
+fn age(now: i64, time: i64) -> String {
+    let seconds = (now - time).max(0);
+    let (amount, unit) = match seconds {
+        0..=59 => return "just now".to_owned(),
+        60..=3_599 => (seconds / 60, "minute"),
+        3_600..=86_399 => (seconds / 3_600, "hour"),
+        86_400..=2_591_999 => (seconds / 86_400, "day"),
+        2_592_000..=31_535_999 => (seconds / 2_592_000, "month"),
+        _ => (seconds / 31_536_000, "year"),
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+    format!("{amount} {unit}{plural} ago")
+}
@@ -0,0 +1,60 @@
+use crate::output::{print_records, Format, Value};
+use crate::utils::Result;
+use clap::ArgMatches;
+use fpm_lib::{config::Config, store::open_store};
+
+pub fn tag(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
+    let mut store = open_store(config)?;
+
+    match sub_matches.subcommand() {
+        Some(("list", sub_matches)) => {
+            let format = sub_matches.get_one::<Format>("format").copied().unwrap_or(Format::Table);
+            let records = store
+                .tag_counts()?
+                .into_iter()
+                .map(|(tag, count)| vec![Value::Text(Some(tag)), Value::Int(Some(count))])
+                .collect::<Vec<_>>();
+            print_records(format, &["tag", "projects"], &["Tag", "Projects"], &records)?;
+        },
+        Some(("rename", sub_matches)) => {
+            let old = sub_matches.get_one::<String>("old").cloned().unwrap_or_default();
+            let new = sub_matches.get_one::<String>("new").cloned().unwrap_or_default();
+            let count = store.rename_tag(&old, &new)?;
+            println!("Renamed `{old}` to `{new}` on {count} projects");
+        },
+        Some(("add", sub_matches)) => {
+            let (key, tags) = project_and_tags(sub_matches);
+            let project = store.find_project(&key)?;
+            for tag in tags {
+                store.add_tag(&project, &tag)?;
+            }
+        },
+        Some(("rm", sub_matches)) => {
+            let (key, tags) = project_and_tags(sub_matches);
+            let project = store.find_project(&key)?;
+            for tag in tags {
+                if !store.remove_tag(&project, &tag)? {
+                    println!("`{key}` does not have the tag `{tag}`");
+                }
+            }
+        },
+        Some(("prune", _)) => {
+            let count = store.prune_tags()?;
+            println!("Removed {count} unused tags");
+        },
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
+
+fn project_and_tags(sub_matches: &ArgMatches) -> (String, Vec<String>) {
+    let key = sub_matches.get_one::<String>("project").cloned().unwrap_or_default();
+    let tags = sub_matches
+        .get_many::<String>("tags")
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+    (key, tags)
+}
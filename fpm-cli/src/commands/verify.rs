@@ -0,0 +1,135 @@
+use crate::output::{print_records, Format, Value};
+use crate::utils::Result;
+use clap::ArgMatches;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Input, Select};
+use fpm_lib::{
+    backup::snapshot,
+    config::Config,
+    store::open_store,
+    verify::{verify_projects, MissingProject},
+};
+use std::path::PathBuf;
+
+pub fn verify(sub_matches: &ArgMatches, config: &Config) -> Result<()> {
+    let format = sub_matches.get_one::<Format>("format").copied().unwrap_or(Format::Table);
+
+    let mut store = open_store(config)?;
+    let projects = store.load_projects()?;
+    let report = verify_projects(&projects);
+
+    if format != Format::Table {
+        return print_missing(&report.missing, format);
+    }
+
+    if report.missing.is_empty() {
+        println!("All {} projects have a valid directory", report.checked);
+        return Ok(());
+    }
+
+    println!(
+        "{} of {} projects are missing their directory",
+        report.missing.len(),
+        report.checked
+    );
+    for missing in &report.missing {
+        println!(
+            "  {} - {}",
+            missing.name.as_deref().unwrap_or_default(),
+            missing.directory.clone().unwrap_or_default().display()
+        );
+    }
+
+    if !console::user_attended() {
+        return Ok(());
+    }
+
+    let mut fixes = vec![];
+    for missing in &report.missing {
+        if let Some(project) = projects.iter().find(|p| p.rowid == missing.rowid) {
+            fixes.push((project.clone(), fix_missing(missing)?));
+        }
+    }
+
+    // Removing projects can not be undone, so keep a copy of the database first
+    if fixes.iter().any(|(_, fix)| matches!(fix, Fix::Remove)) {
+        if let Some(snapshot) = snapshot(config)? {
+            println!("The database was saved to {} before removing projects", snapshot.display());
+        }
+    }
+    for (mut project, fix) in fixes {
+        match fix {
+            Fix::Repoint(dir) => {
+                project.directory = Some(dir);
+                store.update(&project)?;
+            },
+            Fix::Remove => store.remove(&project)?,
+            Fix::Skip => {},
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the missing projects and the folders they may have moved to
+fn print_missing(missing: &[MissingProject], format: Format) -> Result<()> {
+    let records = missing
+        .iter()
+        .map(|missing| {
+            vec![
+                Value::Int(missing.rowid),
+                Value::Text(missing.name.clone()),
+                Value::Text(missing.directory.as_ref().map(|dir| dir.display().to_string())),
+                Value::List(missing.suggestions.iter().map(|dir| dir.display().to_string()).collect()),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    print_records(
+        format,
+        &["id", "name", "directory", "suggestions"],
+        &["ID", "Name", "Directory", "Suggestions"],
+        &records,
+    )
+}
+
+enum Fix {
+    Repoint(PathBuf),
+    Remove,
+    Skip,
+}
+
+fn fix_missing(missing: &MissingProject) -> Result<Fix> {
+    let mut items = missing
+        .suggestions
+        .iter()
+        .map(|dir| format!("Re-point to `{}`", dir.display()))
+        .collect::<Vec<_>>();
+    items.push("Re-point to a different directory".to_owned());
+    items.push("Remove from the database".to_owned());
+    items.push("Skip".to_owned());
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!(
+            "`{}` could not be found, what should be done?",
+            missing.name.as_deref().unwrap_or_default()
+        ))
+        .items(&items)
+        .default(0)
+        .interact()?;
+
+    let suggestions = missing.suggestions.len();
+    let fix = match selection {
+        i if i < suggestions => Fix::Repoint(missing.suggestions[i].clone()),
+        i if i == suggestions => {
+            let dir: String = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt("Project Directory")
+                .interact_text()?;
+            Fix::Repoint(PathBuf::from(dir))
+        },
+        i if i == suggestions + 1 => Fix::Remove,
+        _ => Fix::Skip,
+    };
+
+    Ok(fix)
+}
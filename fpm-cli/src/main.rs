@@ -2,6 +2,7 @@
 
 mod cli;
 mod commands;
+mod output;
 mod utils;
 
 use eyre::Result;
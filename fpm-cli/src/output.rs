@@ -0,0 +1,316 @@
+use crate::utils::Result;
+use clap::{builder::PossibleValue, ValueEnum};
+use fpm_lib::project::Project;
+use prettytable::{format, Cell, Row, Table};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::io::Write;
+
+pub const DEFAULT_COLUMNS: [&str; 3] = ["name", "desc", "directory"];
+
+/// How lists are printed, parsed by clap through [`ValueEnum`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+    Toml,
+    Yaml,
+    Tsv,
+    Plain,
+}
+
+impl ValueEnum for Format {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Self::Table,
+            Self::Json,
+            Self::Csv,
+            Self::Toml,
+            Self::Yaml,
+            Self::Tsv,
+            Self::Plain,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let name = match self {
+            Self::Table => "table",
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Toml => "toml",
+            Self::Yaml => "yaml",
+            Self::Tsv => "tsv",
+            Self::Plain => "plain",
+        };
+        Some(PossibleValue::new(name))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Id,
+    Name,
+    Desc,
+    Directory,
+    Tags,
+    Language,
+    Category,
+    CreatedAt,
+    LastOpenedAt,
+    OpenCount,
+}
+
+impl ValueEnum for Column {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Self::Id,
+            Self::Name,
+            Self::Desc,
+            Self::Directory,
+            Self::Tags,
+            Self::Language,
+            Self::Category,
+            Self::CreatedAt,
+            Self::LastOpenedAt,
+            Self::OpenCount,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let value = PossibleValue::new(self.key());
+        Some(match self {
+            Self::Id => value.alias("rowid"),
+            Self::Directory => value.alias("dir"),
+            _ => value,
+        })
+    }
+}
+
+impl Column {
+    fn title(self) -> &'static str {
+        match self {
+            Self::Id => "Id",
+            Self::Name => "Name",
+            Self::Desc => "Description",
+            Self::Directory => "Directory",
+            Self::Tags => "Tags",
+            Self::Language => "Language",
+            Self::Category => "Category",
+            Self::CreatedAt => "Created At",
+            Self::LastOpenedAt => "Last Opened At",
+            Self::OpenCount => "Open Count",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Self::Id => "id",
+            Self::Name => "name",
+            Self::Desc => "desc",
+            Self::Directory => "directory",
+            Self::Tags => "tags",
+            Self::Language => "language",
+            Self::Category => "category",
+            Self::CreatedAt => "created_at",
+            Self::LastOpenedAt => "last_opened_at",
+            Self::OpenCount => "open_count",
+        }
+    }
+
+    fn value(self, project: &Project) -> Value {
+        match self {
+            Self::Id => Value::Int(project.rowid),
+            Self::Name => Value::Text(project.name.clone()),
+            Self::Desc => Value::Text(project.desc.clone()),
+            Self::Directory => Value::Text(project.directory.as_ref().map(|d| d.display().to_string())),
+            Self::Tags => Value::List(project.tags.clone().unwrap_or_default()),
+            Self::Language => Value::Text(project.language.clone()),
+            Self::Category => Value::Text(project.category.clone()),
+            Self::CreatedAt => Value::Int(project.created_at),
+            Self::LastOpenedAt => Value::Int(project.last_opened_at),
+            Self::OpenCount => Value::Int(project.open_count),
+        }
+    }
+}
+
+pub enum Value {
+    Int(Option<i64>),
+    Text(Option<String>),
+    List(Vec<String>),
+    Bool(Option<bool>),
+}
+
+impl Value {
+    fn is_none(&self) -> bool {
+        matches!(self, Self::Int(None) | Self::Text(None) | Self::Bool(None))
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(int) => write!(f, "{}", int.map(|int| int.to_string()).unwrap_or_default()),
+            Self::Text(text) => write!(f, "{}", text.as_deref().unwrap_or_default()),
+            Self::List(list) => write!(f, "{}", list.join(",")),
+            Self::Bool(bool) => write!(f, "{}", bool.map(|bool| bool.to_string()).unwrap_or_default()),
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        match self {
+            Self::Int(int) => int.serialize(serializer),
+            Self::Text(text) => text.serialize(serializer),
+            Self::List(list) => list.serialize(serializer),
+            Self::Bool(bool) => bool.serialize(serializer),
+        }
+    }
+}
+
+/// A row of values, serialized as a map in column order
+struct Record<'a> {
+    keys: &'a [&'a str],
+    values: &'a [Value],
+    skip_none: bool,
+}
+
+impl Serialize for Record<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (key, value) in self.keys.iter().zip(self.values) {
+            if self.skip_none && value.is_none() {
+                continue;
+            }
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+#[derive(serde::Serialize)]
+struct TomlProjects<'a> {
+    projects: Vec<Record<'a>>,
+}
+
+/// Print `projects` to stdout in the given format, only showing `columns`
+pub fn print_projects(projects: &[Project], format: Format, columns: &[Column]) -> Result<()> {
+    let keys = columns.iter().map(|c| c.key()).collect::<Vec<_>>();
+    let titles = columns.iter().map(|c| c.title()).collect::<Vec<_>>();
+    let records = projects
+        .iter()
+        .map(|project| columns.iter().map(|c| c.value(project)).collect())
+        .collect::<Vec<_>>();
+
+    print_records(format, &keys, &titles, &records)
+}
+
+/// Print `records` to stdout in the given format, the values of a record are in the same order as `keys` and `titles`
+pub fn print_records(format: Format, keys: &[&str], titles: &[&str], records: &[Vec<Value>]) -> Result<()> {
+    write_records(&mut std::io::stdout().lock(), format, keys, titles, records)
+}
+
+fn write_records(out: &mut impl Write, format: Format, keys: &[&str], titles: &[&str], records: &[Vec<Value>]) -> Result<()> {
+    let rows = |skip_none| {
+        records
+            .iter()
+            .map(|values| Record { keys, values, skip_none })
+            .collect::<Vec<_>>()
+    };
+
+    match format {
+        Format::Table => {
+            let mut table = Table::new();
+            table.set_format(*format::consts::FORMAT_BOX_CHARS);
+            table.set_titles(Row::new(titles.iter().map(|title| Cell::new(title)).collect()));
+            for values in records {
+                table.add_row(Row::new(values.iter().map(|v| Cell::new(&v.to_string())).collect()));
+            }
+            table.print(out)?;
+        },
+        Format::Json => writeln!(out, "{}", serde_json::to_string_pretty(&rows(false))?)?,
+        // TOML has no null value and needs a table at the top level
+        Format::Toml => write!(out, "{}", toml::to_string(&TomlProjects { projects: rows(true) })?)?,
+        Format::Yaml => write!(out, "{}", serde_yaml::to_string(&rows(false))?)?,
+        Format::Csv | Format::Tsv => {
+            let delimiter = if format == Format::Csv { b',' } else { b'\t' };
+            let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(&mut *out);
+            writer.write_record(keys)?;
+            for values in records {
+                writer.write_record(values.iter().map(ToString::to_string))?;
+            }
+            writer.flush()?;
+        },
+        Format::Plain => {
+            for values in records {
+                let values = values.iter().map(ToString::to_string).collect::<Vec<_>>();
+                writeln!(out, "{}", values.join(" "))?;
+            }
+        },
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(format: Format) -> Result<String> {
+        let keys = ["name", "tags", "open_count"];
+        let titles = ["Name", "Tags", "Open Count"];
+        let records = vec![
+            vec![
+                Value::Text(Some("fpm".to_owned())),
+                Value::List(vec!["cli".to_owned(), "rust".to_owned()]),
+                Value::Int(Some(3)),
+            ],
+            vec![Value::Text(Some("site".to_owned())), Value::List(vec![]), Value::Int(None)],
+        ];
+
+        let mut out = Vec::new();
+        write_records(&mut out, format, &keys, &titles, &records)?;
+        Ok(String::from_utf8_lossy(&out).into_owned())
+    }
+
+    #[test]
+    fn test_formats() -> Result<()> {
+        let table = output(Format::Table)?;
+        assert!(table.contains("Open Count") && table.contains("cli,rust"));
+
+        let json = serde_json::from_str::<serde_json::Value>(&output(Format::Json)?)?;
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"name": "fpm", "tags": ["cli", "rust"], "open_count": 3},
+                {"name": "site", "tags": [], "open_count": null}
+            ])
+        );
+
+        // TOML leaves out the missing values instead of failing on them
+        assert_eq!(
+            output(Format::Toml)?,
+            "[[projects]]\nname = \"fpm\"\ntags = [\"cli\", \"rust\"]\nopen_count = 3\n\n[[projects]]\nname = \"site\"\ntags = []\n"
+        );
+        assert_eq!(
+            output(Format::Yaml)?,
+            "- name: fpm\n  tags:\n  - cli\n  - rust\n  open_count: 3\n- name: site\n  tags: []\n  open_count: null\n"
+        );
+        assert_eq!(output(Format::Csv)?, "name,tags,open_count\nfpm,\"cli,rust\",3\nsite,,\n");
+        assert_eq!(output(Format::Tsv)?, "name\ttags\topen_count\nfpm\tcli,rust\t3\nsite\t\t\n");
+        assert_eq!(output(Format::Plain)?, "fpm cli,rust 3\nsite  \n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse() {
+        for format in Format::value_variants() {
+            let name = format.to_possible_value().map(|value| value.get_name().to_owned());
+            assert_eq!(Format::from_str(&name.unwrap_or_default(), false).ok(), Some(*format));
+        }
+        assert_eq!(Column::from_str("dir", false).ok(), Some(Column::Directory));
+        assert_eq!(Column::from_str("rowid", false).ok(), Some(Column::Id));
+        assert!(Format::from_str("xml", false).is_err());
+    }
+}
@@ -11,6 +11,24 @@ pub enum Error {
     #[error(transparent)]
     Indicatif(#[from] indicatif::style::TemplateError),
 
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+
+    #[error(transparent)]
+    Toml(#[from] toml::ser::Error),
+
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("`{0}` is not set")]
+    NotSet(String),
+
+    #[error("found {0} problem(s) in the config")]
+    InvalidConfig(usize),
+
     #[error(transparent)]
     Fpm(#[from] fpm_lib::error::Error),
 }
@@ -0,0 +1,236 @@
+//! Backups of the project database.
+//!
+//! Backups go through SQLite's online backup API, so a copy is consistent even if another process is writing to the
+//! database at the same time. Automatic snapshots are taken before destructive operations and named
+//! `snapshot-<unix timestamp>.db` (or the extension of the project file) in [`Config::backup_folder`], only the newest
+//! [`Config::backup_retention`] of them are kept.
+//!
+//! With the `file` backend the project file is copied instead.
+
+use crate::{
+    config::Config,
+    error::Error,
+    store::{Backend, FileStore},
+    utils,
+};
+use fs_err as fs;
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use std::path::{Path, PathBuf};
+
+const SNAPSHOT_PREFIX: &str = "snapshot-";
+
+/// Copy the database to `path`, overwriting anything already there
+pub fn backup_database(config: &Config, path: &Path) -> utils::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+
+    match config.backend {
+        Backend::Sqlite => {
+            let conn = Connection::open_with_flags(&config.database_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+            conn.backup(DatabaseName::Main, path, None)?;
+        },
+        Backend::File => {
+            fs::copy(&config.database_path, path)?;
+        },
+    }
+    Ok(())
+}
+
+/// Where `fpm db backup` writes to when no path is given, a timestamped file in the backup folder
+///
+/// These are not snapshots, so they are never deleted automatically
+pub fn default_backup_path(config: &Config) -> PathBuf {
+    config
+        .backup_folder()
+        .join(format!("backup-{}.{}", utils::now(), extension(config)))
+}
+
+/// Replace the database with the backup at `path`, taking a snapshot of the current database first
+///
+/// Returns the path of the snapshot, if there was a database to take one of
+pub fn restore_database(config: &Config, path: &Path) -> utils::Result<Option<PathBuf>> {
+    check_backup(config, path)?;
+    let snapshot = snapshot(config)?;
+
+    if let Some(dir) = Path::new(&config.database_path).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    match config.backend {
+        Backend::Sqlite => {
+            let mut conn = Connection::open(&config.database_path)?;
+            conn.restore(DatabaseName::Main, path, None::<fn(rusqlite::backup::Progress)>)?;
+        },
+        Backend::File => {
+            fs::copy(path, &config.database_path)?;
+        },
+    }
+    Ok(snapshot)
+}
+
+/// Take a timestamped snapshot of the database and delete the snapshots past the retention count
+///
+/// Returns `None` without doing anything if the database does not exist yet
+pub fn snapshot(config: &Config) -> utils::Result<Option<PathBuf>> {
+    if !Path::new(&config.database_path).exists() {
+        return Ok(None);
+    }
+
+    // Always sort after the existing snapshots, even if several are taken within a second
+    let now = utils::now();
+    let path = match snapshots(config)?.last() {
+        Some(&((timestamp, n), _)) if timestamp >= now => {
+            config
+                .backup_folder()
+                .join(format!("{SNAPSHOT_PREFIX}{timestamp}-{}.{}", n + 1, extension(config)))
+        },
+        _ => config
+            .backup_folder()
+            .join(format!("{SNAPSHOT_PREFIX}{now}.{}", extension(config))),
+    };
+
+    backup_database(config, &path)?;
+    prune_snapshots(config)?;
+    Ok(Some(path))
+}
+
+/// The snapshots in the backup folder, oldest first
+pub fn list_snapshots(config: &Config) -> utils::Result<Vec<PathBuf>> {
+    Ok(snapshots(config)?.into_iter().map(|(_, path)| path).collect())
+}
+
+/// The snapshots in the backup folder with their sort keys, oldest first
+fn snapshots(config: &Config) -> utils::Result<Vec<((i64, u32), PathBuf)>> {
+    let folder = config.backup_folder();
+    if !folder.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut snapshots = vec![];
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        if let Some(key) = snapshot_key(&path) {
+            snapshots.push((key, path));
+        }
+    }
+    snapshots.sort();
+    Ok(snapshots)
+}
+
+/// Delete the oldest snapshots so only `backup_retention` of them are left
+fn prune_snapshots(config: &Config) -> utils::Result<()> {
+    let snapshots = list_snapshots(config)?;
+    let excess = snapshots.len().saturating_sub(config.backup_retention());
+    for path in &snapshots[..excess] {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Sort key of a snapshot file, the timestamp and the counter added when two snapshots share a timestamp
+fn snapshot_key(path: &Path) -> Option<(i64, u32)> {
+    let stem = path.file_stem()?.to_str()?.strip_prefix(SNAPSHOT_PREFIX)?;
+    match stem.split_once('-') {
+        Some((timestamp, n)) => Some((timestamp.parse().ok()?, n.parse().ok()?)),
+        None => Some((stem.parse().ok()?, 0)),
+    }
+}
+
+/// Backups keep the extension of the database, so a JSON or TOML project file stays recognizable
+fn extension(config: &Config) -> &str {
+    Path::new(&config.database_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("db")
+}
+
+/// Make sure `path` is a database with a `project` table, or a readable project file, before restoring it
+fn check_backup(config: &Config, path: &Path) -> utils::Result<()> {
+    if !path.is_file() {
+        return Err(Error::InvalidBackup(path.to_path_buf()));
+    }
+    if config.backend == Backend::File {
+        return match FileStore::open(path.to_path_buf()) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(Error::InvalidBackup(path.to_path_buf())),
+        };
+    }
+
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let tables: i64 = conn
+        .query_row(
+            "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = 'project'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|_| Error::InvalidBackup(path.to_path_buf()))?;
+    if tables == 0 {
+        return Err(Error::InvalidBackup(path.to_path_buf()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_backup_restore() -> Result<()> {
+        let root = PathBuf::from("./tests/test_files/backup");
+        if root.exists() {
+            fs::remove_dir_all(&root)?;
+        }
+        fs::create_dir_all(&root)?;
+
+        let config = Config {
+            database_path: root.join("projects.db").to_str().unwrap_or_default().to_owned(),
+            backup_dir: Some(root.join("backups").to_str().unwrap_or_default().to_owned()),
+            backup_retention: Some(2),
+            ..Config::new()
+        };
+        assert_eq!(snapshot(&config)?, None);
+
+        let conn = Connection::open(&config.database_path)?;
+        conn.execute_batch("CREATE TABLE project (name TEXT); INSERT INTO project VALUES ('first');")?;
+
+        let backup = root.join("manual.db");
+        backup_database(&config, &backup)?;
+        conn.execute("UPDATE project SET name = 'second'", [])?;
+
+        for _ in 0..3 {
+            snapshot(&config)?;
+        }
+        let snapshots = list_snapshots(&config)?;
+        assert_eq!(snapshots.len(), 2);
+
+        assert!(restore_database(&config, &backup)?.is_some());
+        let name: String = conn.query_row("SELECT name FROM project", [], |row| row.get(0))?;
+        assert_eq!(name, "first");
+
+        // The snapshot taken by the restore replaced the oldest one
+        let after = list_snapshots(&config)?;
+        assert_eq!(after.len(), 2);
+        assert_eq!(after[0], snapshots[1]);
+
+        // A retention of 0 still keeps the snapshot that was just taken
+        let keep_none = Config {
+            database_path: config.database_path.clone(),
+            backup_dir: config.backup_dir.clone(),
+            backup_retention: Some(0),
+            ..Config::new()
+        };
+        let taken = snapshot(&keep_none)?;
+        assert_eq!(list_snapshots(&config)?.last(), taken.as_ref());
+        assert_eq!(list_snapshots(&config)?.len(), 1);
+
+        let not_a_project_db = root.join("other.db");
+        Connection::open(&not_a_project_db)?.execute_batch("CREATE TABLE other (id INTEGER)")?;
+        assert!(matches!(
+            restore_database(&config, &not_a_project_db),
+            Err(Error::InvalidBackup(_))
+        ));
+
+        Ok(())
+    }
+}
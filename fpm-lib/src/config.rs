@@ -1,15 +1,67 @@
 use crate::error::Error;
+use crate::layout::{render_layout, DEFAULT_LAYOUT};
 use crate::project::Project;
+use crate::store::Backend;
 use crate::utils::{config_folder, Result};
+use crate::validate::ensure_within;
 use fs_err as fs;
 use serde::{Deserialize, Serialize};
-use std::io::Write;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Name of the global config file inside the config folder
+pub const CONFIG_FILE: &str = "fpm.toml";
+/// Name of the repo-local config file, searched for in the current directory and its parents
+pub const LOCAL_CONFIG_FILE: &str = ".fpm.toml";
+/// Prefix of the environment variables that override config values, I.E. `FPM_BASE_DIR`
+pub const ENV_PREFIX: &str = "FPM_";
+
+/// The keys a repo-local config may set. A cloned repository is not trusted, so it can not change the commands fpm
+/// runs or the folders and database it writes to.
+pub const LOCAL_KEYS: [&str; 4] = ["layout", "layouts", "template_groups", "default_category"];
+
+/// The keys that do not hold strings, the values given for them by [`parse_value`] are read as TOML
+const NON_STRING_KEYS: [&str; 2] = ["backup_retention", "template_groups"];
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
+    /// The SQLite database, or the project file when using the `file` backend
     pub database_path: String,
     pub base_dir: Option<String>,
+    /// Folder containing the project templates, defaults to `templates` in the config folder
+    #[serde(default)]
+    pub template_dir: Option<String>,
+    /// Named lists of templates that are applied in order, I.E. `rust = ["base", "rust"]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub template_groups: BTreeMap<String, Vec<String>>,
+    /// Command used to clone git repositories, `{url}` and `{dir}` are replaced with the repository and target folder
+    #[serde(default)]
+    pub git_command: Option<String>,
+    /// Command used to open projects, `{dir}` is replaced with the project directory, I.E. `code {dir}`
+    #[serde(default)]
+    pub editor: Option<String>,
+    /// Open commands for specific categories or languages, these take precedence over `editor`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub open: BTreeMap<String, String>,
+    /// Folder the database backups are written to, defaults to `backups` in the config folder
+    #[serde(default)]
+    pub backup_dir: Option<String>,
+    /// How many automatic snapshots to keep before the oldest are deleted, defaults to 10
+    #[serde(default)]
+    pub backup_retention: Option<usize>,
+    /// Folder structure of generated projects inside `base_dir`, see [`crate::layout`]
+    #[serde(default)]
+    pub layout: Option<String>,
+    /// Layouts for specific categories, these take precedence over `layout`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub layouts: BTreeMap<String, String>,
+    /// Where the projects are stored, see [`crate::store`]
+    #[serde(default)]
+    pub backend: Backend,
+    /// Category given to new and added projects when none is passed
+    #[serde(default)]
+    pub default_category: Option<String>,
 }
 
 impl Default for Config {
@@ -28,33 +80,71 @@ impl Config {
         Self {
             database_path: db_path.to_str().unwrap_or_default().to_owned(),
             base_dir: None,
+            template_dir: None,
+            template_groups: BTreeMap::new(),
+            git_command: None,
+            editor: None,
+            open: BTreeMap::new(),
+            backup_dir: None,
+            backup_retention: None,
+            layout: None,
+            layouts: BTreeMap::new(),
+            backend: Backend::default(),
+            default_category: None,
         }
     }
 
+    /// Load the config from the default [`ConfigSources`]
     pub fn load() -> Result<Self> {
-        let cf = config_folder();
-        let mut config_path = cf;
-        config_path.push("fpm.toml");
+        Self::load_with(&ConfigSources::discover(None))
+    }
 
-        let content = match fs::read_to_string(config_path) {
-            Ok(c) => c,
-            Err(e) => return Err(Error::IO(e)),
-        };
+    /// Load the config by layering `sources` on top of the defaults of [`Config::new`]
+    pub fn load_with(sources: &ConfigSources) -> Result<Self> {
+        let mut merged = toml::Value::try_from(Self::new())?;
 
-        match toml::from_str::<Config>(&content) {
-            Ok(c) => Ok(c),
-            Err(e) => Err(Error::TomlDes(e)),
+        for (path, required) in sources.files() {
+            let content = match fs::read_to_string(path) {
+                Ok(content) => content,
+                Err(e) if !required && e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(Error::IO(e)),
+            };
+            let mut table = toml::from_str::<toml::Table>(&content)?;
+            if Some(path) == sources.local.as_deref() {
+                table = table
+                    .into_iter()
+                    .filter(|(key, _)| LOCAL_KEYS.contains(&key.as_str()))
+                    .collect();
+            }
+            merge(&mut merged, toml::Value::Table(table));
+        }
+        for (key, value) in &sources.env {
+            merge(&mut merged, env_value(key, value));
         }
+
+        Ok(merged.try_into()?)
+    }
+
+    /// Parse the contents of a single config file on top of the defaults of [`Config::new`]
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut merged = toml::Value::try_from(Self::new())?;
+        merge(&mut merged, toml::from_str(content)?);
+        Ok(merged.try_into()?)
     }
 
     pub fn save(&self) -> Result<()> {
-        let cf = config_folder();
-        let mut config_path = cf.clone();
-        config_path.push("fpm.toml");
+        let mut config_path = config_folder();
+        config_path.push(CONFIG_FILE);
+        self.save_to(&config_path)
+    }
 
-        fs::create_dir_all(cf)?;
+    /// Write the config to `path`, creating its folder if needed
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
 
-        let mut file = match fs::File::create(config_path) {
+        let mut file = match fs::File::create(path) {
             Ok(f) => f,
             Err(e) => return Err(Error::IO(e)),
         };
@@ -64,7 +154,7 @@ impl Config {
             Err(e) => return Err(Error::TomlSer(e)),
         };
 
-        match file.write(serialized.as_bytes()) {
+        match file.write_all(serialized.as_bytes()) {
             Ok(_) => {},
             Err(e) => return Err(Error::IO(e)),
         };
@@ -72,25 +162,270 @@ impl Config {
         Ok(())
     }
 
-    pub fn gen_project_folder(&self, project: &Project) -> Result<PathBuf> {
-        let mut path = PathBuf::new();
+    /// The suggested `base_dir` for a new setup, `projects` in the home folder
+    pub fn default_base_dir() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join("projects"))
+    }
 
-        if let Some(base_dir) = &self.base_dir {
-            path.push(base_dir);
-        } else {
-            return Err(Error::ConfigMissingValue("base_dir".to_owned()));
-        }
+    /// The command used to clone repositories, `--` keeps git from reading the url or folder as an option
+    pub fn git_command(&self) -> &str {
+        self.git_command.as_deref().unwrap_or("git clone -- {url} {dir}")
+    }
 
-        if let Some(cat) = &project.category {
-            path.push(cat);
+    pub fn template_folder(&self) -> PathBuf {
+        match &self.template_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => {
+                let mut dir = config_folder();
+                dir.push("templates");
+                dir
+            },
         }
-        if let Some(lang) = &project.language {
-            path.push(lang);
+    }
+
+    pub fn backup_folder(&self) -> PathBuf {
+        match &self.backup_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => {
+                let mut dir = config_folder();
+                dir.push("backups");
+                dir
+            },
         }
-        if let Some(name) = &project.name {
-            path.push(name);
+    }
+
+    /// At least one snapshot is always kept, otherwise the one just taken would be deleted right away
+    pub fn backup_retention(&self) -> usize {
+        self.backup_retention.unwrap_or(10).max(1)
+    }
+
+    /// The layout for `project`, the one for its category if there is one
+    pub fn layout(&self, project: &Project) -> &str {
+        project
+            .category
+            .as_ref()
+            .and_then(|category| self.layouts.get(category))
+            .or(self.layout.as_ref())
+            .map_or(DEFAULT_LAYOUT, String::as_str)
+    }
+
+    pub fn gen_project_folder(&self, project: &Project) -> Result<PathBuf> {
+        let Some(base_dir) = &self.base_dir else {
+            return Err(Error::ConfigMissingValue("base_dir".to_owned()));
+        };
+
+        let relative = render_layout(self.layout(project), project)?;
+        ensure_within(Path::new(base_dir), &relative)
+    }
+}
+
+/// Where the config is loaded from. Later sources override earlier ones:
+///
+/// 1. The defaults of [`Config::new`]
+/// 2. The global `fpm.toml` in the config folder
+/// 3. A repo-local `.fpm.toml` in the current directory or one of its parents, only its [`LOCAL_KEYS`] are used
+/// 4. `FPM_*` environment variables
+/// 5. The file passed with `--config`
+///
+/// Missing global and local files are skipped, a missing `--config` file is an error.
+#[derive(Debug, Default, Clone)]
+pub struct ConfigSources {
+    pub global: Option<PathBuf>,
+    pub local: Option<PathBuf>,
+    /// Environment variables with the `FPM_` prefix, see [`env_value`] for how they are read
+    pub env: Vec<(String, String)>,
+    pub explicit: Option<PathBuf>,
+}
+
+impl ConfigSources {
+    /// The sources for the current user, directory and environment
+    pub fn discover(explicit: Option<PathBuf>) -> Self {
+        let mut global = config_folder();
+        global.push(CONFIG_FILE);
+
+        Self {
+            global: Some(global),
+            local: std::env::current_dir().ok().and_then(|dir| find_local_config(&dir)),
+            env: std::env::vars().filter(|(key, _)| key.starts_with(ENV_PREFIX)).collect(),
+            explicit,
         }
+    }
 
-        Ok(path)
+    /// The file that config changes are written to, the `--config` file if there is one
+    pub fn target(&self) -> Option<&Path> {
+        self.explicit.as_deref().or(self.global.as_deref())
+    }
+
+    /// Whether any of the config files exist, if none do fpm has not been set up yet
+    pub fn any_exist(&self) -> bool {
+        self.files().any(|(path, _)| path.is_file())
+    }
+
+    /// The config files in the order they are applied, and whether each one has to exist
+    pub fn files(&self) -> impl Iterator<Item = (&Path, bool)> {
+        [(&self.global, false), (&self.local, false), (&self.explicit, true)]
+            .into_iter()
+            .filter_map(|(path, required)| path.as_deref().map(|path| (path, required)))
+    }
+}
+
+/// Find the closest `.fpm.toml`, starting in `dir` and walking up through its parents
+pub fn find_local_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(LOCAL_CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Merge `layer` into `base`, tables are merged key by key and any other value is replaced
+fn merge(base: &mut toml::Value, layer: toml::Value) {
+    match (base, layer) {
+        (toml::Value::Table(base), toml::Value::Table(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    },
+                }
+            }
+        },
+        (base, layer) => *base = layer,
+    }
+}
+
+/// Turn an environment variable into a config table.
+///
+/// The key is lowercased after the `FPM_` prefix and `__` separates nested keys, so
+/// `FPM_OPEN__RUST` sets `open.rust`. The value is read with [`parse_value`].
+fn env_value(key: &str, value: &str) -> toml::Value {
+    let key = key.strip_prefix(ENV_PREFIX).unwrap_or(key).to_lowercase();
+    let mut value = parse_value(&key.replace("__", "."), value);
+
+    for part in key.rsplit("__") {
+        let mut table = toml::Table::new();
+        table.insert(part.to_owned(), value);
+        value = toml::Value::Table(table);
+    }
+    value
+}
+
+/// Read a value for `key` given on the command line or in the environment.
+///
+/// A quoted TOML string is unquoted. Anything else is used as a plain string, so `2024` can be a category, unless
+/// `key` holds a number or a list, then the value is read as TOML.
+pub fn parse_value(key: &str, raw: &str) -> toml::Value {
+    let parsed = toml::from_str::<toml::Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"));
+    let field = key.split('.').next().unwrap_or(key);
+
+    match parsed {
+        Some(toml::Value::String(value)) => toml::Value::String(value),
+        Some(parsed) if NON_STRING_KEYS.contains(&field) => parsed,
+        _ => toml::Value::String(raw.to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_layered_load() -> Result<()> {
+        let root = PathBuf::from("./tests/test_files/layered_config");
+        let _ = std::fs::remove_dir_all(&root);
+        let nested = root.join("repo").join("src");
+        fs::create_dir_all(&nested)?;
+
+        let global = root.join(CONFIG_FILE);
+        fs::write(
+            &global,
+            "base_dir = \"/global\"\neditor = \"vim {dir}\"\n[open]\nrust = \"code {dir}\"\n",
+        )?;
+        fs::write(
+            root.join("repo").join(LOCAL_CONFIG_FILE),
+            "base_dir = \"/local\"\nlayout = \"{name}\"\ngit_command = \"sh -c {url}\"\n",
+        )?;
+        let explicit = root.join("explicit.toml");
+        fs::write(&explicit, "layout = \"{category}/{name}\"\n")?;
+
+        // Nothing exists, so only the defaults are used
+        let config = Config::load_with(&ConfigSources {
+            global: Some(root.join("missing.toml")),
+            ..ConfigSources::default()
+        })?;
+        assert_eq!(config.database_path, Config::new().database_path);
+        assert_eq!(config.base_dir, None);
+
+        let local = find_local_config(&nested);
+        assert_eq!(local, Some(root.join("repo").join(LOCAL_CONFIG_FILE)));
+
+        let mut sources = ConfigSources {
+            global: Some(global),
+            local,
+            env: vec![
+                ("FPM_EDITOR".to_owned(), "hx {dir}".to_owned()),
+                ("FPM_BACKUP_RETENTION".to_owned(), "3".to_owned()),
+                ("FPM_OPEN__PYTHON".to_owned(), "pycharm {dir}".to_owned()),
+                ("FPM_DEFAULT_CATEGORY".to_owned(), "2024".to_owned()),
+            ],
+            explicit: None,
+        };
+        let config = Config::load_with(&sources)?;
+        // The local config can change the layout, but not where projects go or which commands run
+        assert_eq!(config.base_dir.as_deref(), Some("/global"));
+        assert_eq!(config.git_command, None);
+        assert_eq!(config.layout.as_deref(), Some("{name}"));
+        assert_eq!(config.editor.as_deref(), Some("hx {dir}"));
+        assert_eq!(config.backup_retention, Some(3));
+        assert_eq!(config.open.get("rust").map(String::as_str), Some("code {dir}"));
+        assert_eq!(config.open.get("python").map(String::as_str), Some("pycharm {dir}"));
+        assert_eq!(config.default_category.as_deref(), Some("2024"));
+
+        sources.explicit = Some(explicit);
+        let config = Config::load_with(&sources)?;
+        assert_eq!(config.layout.as_deref(), Some("{category}/{name}"));
+        assert_eq!(config.base_dir.as_deref(), Some("/global"));
+
+        sources.explicit = Some(root.join("missing.toml"));
+        assert!(matches!(Config::load_with(&sources), Err(Error::IO(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_value() {
+        let string = |value: &str| toml::Value::String(value.to_owned());
+        assert_eq!(parse_value("default_category", "2024"), string("2024"));
+        assert_eq!(parse_value("default_category", "\"2024\""), string("2024"));
+        assert_eq!(parse_value("editor", "true"), string("true"));
+        assert_eq!(parse_value("open.rust", "code {dir}"), string("code {dir}"));
+        assert_eq!(parse_value("backup_retention", "3"), toml::Value::Integer(3));
+        assert_eq!(parse_value("backup_retention", "three"), string("three"));
+        assert_eq!(
+            parse_value("template_groups.web", "[\"html\"]"),
+            toml::Value::Array(vec![string("html")])
+        );
+    }
+
+    #[test]
+    fn test_save_to() -> Result<()> {
+        let path = PathBuf::from("./tests/test_files/save_config").join(CONFIG_FILE);
+        let _ = std::fs::remove_file(&path);
+
+        let config = Config {
+            base_dir: Some("/projects".to_owned()),
+            default_category: Some("personal".to_owned()),
+            ..Config::new()
+        };
+        config.save_to(&path)?;
+
+        let loaded = Config::parse(&fs::read_to_string(&path)?)?;
+        assert_eq!(loaded.base_dir, config.base_dir);
+        assert_eq!(loaded.default_category, config.default_category);
+        assert_eq!(loaded.database_path, config.database_path);
+
+        Ok(())
     }
 }
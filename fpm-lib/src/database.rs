@@ -1,56 +1,380 @@
-use crate::{config::Config, project::Project, utils};
+use crate::{
+    backup,
+    config::Config,
+    error::Error,
+    project::Project,
+    store::{ProjectFilter, ProjectStore},
+    utils,
+};
 use fs_err as fs;
-use std::path::PathBuf;
-use std::sync::Once;
-use turbosql::{select, set_db_path, Turbosql};
+use rusqlite::{params, Connection, Row, Transaction, TransactionBehavior};
+use std::path::{Path, PathBuf};
 
-static DB_INIT: Once = Once::new();
+/// Schema changes applied in order to every database, new migrations may only be added to the end.
+///
+/// These were originally applied by turbosql, so the applied migrations are still tracked in its
+/// `_turbosql_migrations` table and the `directory` column holds JSON encoded paths.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE project (rowid INTEGER PRIMARY KEY) STRICT",
+    "ALTER TABLE project ADD COLUMN name TEXT",
+    "ALTER TABLE project ADD COLUMN desc TEXT",
+    "ALTER TABLE project ADD COLUMN directory TEXT",
+    "ALTER TABLE project ADD COLUMN tags TEXT",
+    "ALTER TABLE project ADD COLUMN language TEXT",
+    "ALTER TABLE project ADD COLUMN category TEXT",
+    "ALTER TABLE project ADD COLUMN created_at INTEGER",
+    "ALTER TABLE project ADD COLUMN last_opened_at INTEGER",
+    "ALTER TABLE project ADD COLUMN open_count INTEGER",
+    "CREATE TABLE project_tag (rowid INTEGER PRIMARY KEY, project_rowid INTEGER NOT NULL, tag TEXT NOT NULL) STRICT",
+    "CREATE UNIQUE INDEX project_tag_project_rowid_tag ON project_tag (project_rowid, tag)",
+    "CREATE INDEX project_tag_tag ON project_tag (tag)",
+    "INSERT OR IGNORE INTO project_tag (project_rowid, tag) SELECT project.rowid, json_each.value FROM project, json_each(project.tags) WHERE json_type(project.tags) = 'array'",
+    "UPDATE project SET tags = 'null'",
+];
 
-fn set_db(config: &Config) -> utils::Result<()> {
-    let path = PathBuf::from(&config.database_path);
+const PROJECT_COLUMNS: &str = "rowid, name, desc, directory, language, category, created_at, last_opened_at, open_count";
 
-    let mut dir = path.clone();
-    dir.pop();
+/// The SQLite [`ProjectStore`], the default backend
+pub struct ProjectDb {
+    conn: Connection,
+}
 
-    fs::create_dir_all(dir)?;
+impl ProjectDb {
+    /// Open the database at `config.database_path`, creating it if it does not exist yet
+    pub fn open(config: &Config) -> utils::Result<Self> {
+        Self::open_path(Path::new(&config.database_path))
+    }
 
-    DB_INIT.call_once(|| {
-        match set_db_path(path.as_path()) {
-            Ok(_) => {},
-            Err(e) => panic!("{e}"),
-        };
-    });
-    Ok(())
+    pub fn open_path(path: &Path) -> utils::Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "PRAGMA busy_timeout = 3000;
+            PRAGMA journal_mode = WAL;
+            PRAGMA synchronous = NORMAL;",
+        )?;
+        Self::from_connection(conn)
+    }
+
+    /// A database that only lives as long as the returned handle
+    pub fn open_in_memory() -> utils::Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> utils::Result<Self> {
+        let mut db = Self { conn };
+        db.migrate()?;
+        Ok(db)
+    }
+
+    /// Apply the migrations that have not been applied to this database yet
+    fn migrate(&mut self) -> utils::Result<()> {
+        let tx = self.conn.transaction_with_behavior(TransactionBehavior::Exclusive)?;
+        tx.execute(
+            "CREATE TABLE IF NOT EXISTS _turbosql_migrations (rowid INTEGER PRIMARY KEY, migration TEXT NOT NULL) STRICT",
+            [],
+        )?;
+
+        let applied = tx
+            .prepare("SELECT migration FROM _turbosql_migrations ORDER BY rowid")?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if applied.len() > MIGRATIONS.len() {
+            return Err(Error::Migration(
+                "the database was created by a newer version of fpm".to_owned(),
+            ));
+        }
+        if let Some((applied, expected)) = applied.iter().zip(MIGRATIONS).find(|(a, e)| a != *e) {
+            return Err(Error::Migration(format!("expected `{expected}` but found `{applied}`")));
+        }
+
+        for migration in &MIGRATIONS[applied.len()..] {
+            tx.execute(migration, [])?;
+            tx.execute("INSERT INTO _turbosql_migrations (migration) VALUES (?)", [migration])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Load the projects matching the `clause` following `FROM project`, along with their tags
+    fn select_projects(&self, clause: &str, params: impl rusqlite::Params) -> utils::Result<Vec<Project>> {
+        let mut projects = self
+            .conn
+            .prepare(&format!("SELECT {PROJECT_COLUMNS} FROM project {clause}"))?
+            .query_map(params, project_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut statement = self
+            .conn
+            .prepare("SELECT tag FROM project_tag WHERE project_rowid = ? ORDER BY rowid")?;
+        for project in &mut projects {
+            let tags = statement
+                .query_map([project.rowid], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            project.tags = Some(tags);
+        }
+
+        Ok(projects)
+    }
 }
 
-pub fn add_project(config: &Config, project: &Project) -> utils::Result<()> {
-    set_db(config)?;
+impl ProjectStore for ProjectDb {
+    fn add(&mut self, project: &Project) -> utils::Result<i64> {
+        let tx = self.conn.transaction()?;
+        let rowid = insert_project(&tx, project)?;
+        tx.commit()?;
+        Ok(rowid)
+    }
 
-    project.insert()?;
+    fn get(&self, rowid: i64) -> utils::Result<Option<Project>> {
+        Ok(self.select_projects("WHERE rowid = ?", [rowid])?.into_iter().next())
+    }
 
-    Ok(())
+    fn update(&mut self, project: &Project) -> utils::Result<()> {
+        let Some(rowid) = project.rowid else {
+            return Err(Error::Static(
+                "cannot update a project that has not been added to the database",
+            ));
+        };
+
+        let tx = self.conn.transaction()?;
+        update_project(&tx, rowid, project)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn remove(&mut self, project: &Project) -> utils::Result<()> {
+        let Some(rowid) = project.rowid else {
+            return Err(Error::Static(
+                "cannot remove a project that has not been added to the database",
+            ));
+        };
+
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM project_tag WHERE project_rowid = ?", [rowid])?;
+        tx.execute("DELETE FROM project WHERE rowid = ?", [rowid])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// The filtering is done by the database
+    fn query(&self, filter: &ProjectFilter) -> utils::Result<Vec<Project>> {
+        // `%` and `_` in the search text are matched literally, like the other backends do
+        let search = filter.search.as_ref().map(|s| {
+            let escaped = s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+            format!("%{escaped}%")
+        });
+        let tags = serde_json::to_string(&filter.tags)?;
+
+        self.select_projects(
+            "WHERE (?1 IS NULL OR language = ?1 COLLATE NOCASE)
+            AND (?2 IS NULL OR category = ?2 COLLATE NOCASE)
+            AND (?3 IS NULL OR name GLOB ?3)
+            AND (?4 IS NULL OR name LIKE ?4 ESCAPE '\\' OR desc LIKE ?4 ESCAPE '\\')
+            AND NOT EXISTS (
+                SELECT 1 FROM json_each(?5) AS wanted
+                WHERE wanted.value NOT IN (SELECT tag FROM project_tag WHERE project_rowid = project.rowid)
+            )",
+            params![filter.language, filter.category, filter.name, search, tags],
+        )
+    }
+
+    /// Either all of the projects are added or none are
+    fn add_projects(&mut self, projects: &[Project]) -> utils::Result<()> {
+        let tx = self.conn.transaction()?;
+        for project in projects {
+            insert_project(&tx, project)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn load_projects(&self) -> utils::Result<Vec<Project>> {
+        self.select_projects("", [])
+    }
+
+    /// Everything is done in one transaction
+    fn apply_changes(&mut self, add: &[Project], update: &[Project]) -> utils::Result<()> {
+        let tx = self.conn.transaction()?;
+        for project in add {
+            insert_project(&tx, project)?;
+        }
+        for project in update {
+            let Some(rowid) = project.rowid else {
+                return Err(Error::Static(
+                    "cannot update a project that has not been added to the database",
+                ));
+            };
+            update_project(&tx, rowid, project)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn tag_counts(&self) -> utils::Result<Vec<(String, i64)>> {
+        let counts = self
+            .conn
+            .prepare("SELECT tag, COUNT(*) FROM project_tag GROUP BY tag ORDER BY tag")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(counts)
+    }
+
+    fn rename_tag(&mut self, old: &str, new: &str) -> utils::Result<usize> {
+        // Renaming a tag to itself would delete it after the update is ignored
+        if old == new {
+            return Ok(0);
+        }
+        let tx = self.conn.transaction()?;
+        let renamed = tx.execute("UPDATE OR IGNORE project_tag SET tag = ? WHERE tag = ?", [new, old])?;
+        let merged = tx.execute("DELETE FROM project_tag WHERE tag = ?", [old])?;
+        tx.commit()?;
+        Ok(renamed + merged)
+    }
+
+    fn add_tag(&mut self, project: &Project, tag: &str) -> utils::Result<()> {
+        let Some(rowid) = project.rowid else {
+            return Err(Error::Static("cannot tag a project that has not been added to the database"));
+        };
+        self.conn.execute(
+            "INSERT OR IGNORE INTO project_tag (project_rowid, tag) VALUES (?, ?)",
+            params![rowid, tag],
+        )?;
+
+        Ok(())
+    }
+
+    fn remove_tag(&mut self, project: &Project, tag: &str) -> utils::Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM project_tag WHERE project_rowid = ? AND tag = ?",
+            params![project.rowid, tag],
+        )?;
+
+        Ok(removed > 0)
+    }
+
+    /// Also removes the tags of projects that no longer exist
+    fn prune_tags(&mut self) -> utils::Result<usize> {
+        let tx = self.conn.transaction()?;
+        let orphaned = tx.execute(
+            "DELETE FROM project_tag WHERE project_rowid NOT IN (SELECT rowid FROM project)",
+            [],
+        )?;
+        let blank = tx.execute("DELETE FROM project_tag WHERE trim(tag) = ''", [])?;
+        tx.commit()?;
+        Ok(orphaned + blank)
+    }
 }
 
-pub fn load_projects(config: &Config) -> utils::Result<Vec<Project>> {
-    set_db(config)?;
+fn insert_project(tx: &Transaction, project: &Project) -> utils::Result<i64> {
+    if project.rowid.is_some() {
+        return Err(Error::Static("cannot add a project that is already in the database"));
+    }
 
-    let projects = select!(Vec<Project>);
+    tx.execute(
+        "INSERT INTO project (name, desc, directory, language, category, created_at, last_opened_at, open_count)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            project.name,
+            project.desc,
+            directory_to_sql(project)?,
+            project.language,
+            project.category,
+            project.created_at,
+            project.last_opened_at,
+            project.open_count
+        ],
+    )?;
+    let rowid = tx.last_insert_rowid();
+    save_tags(tx, rowid, project.tags.as_deref().unwrap_or_default())?;
+    Ok(rowid)
+}
 
-    match projects {
-        Ok(projects) => Ok(projects),
-        Err(e) => Err(crate::error::Error::Sql(e)),
+fn update_project(tx: &Transaction, rowid: i64, project: &Project) -> utils::Result<()> {
+    let changed = tx.execute(
+        "UPDATE project SET name = ?, desc = ?, directory = ?, language = ?, category = ?,
+        created_at = ?, last_opened_at = ?, open_count = ? WHERE rowid = ?",
+        params![
+            project.name,
+            project.desc,
+            directory_to_sql(project)?,
+            project.language,
+            project.category,
+            project.created_at,
+            project.last_opened_at,
+            project.open_count,
+            rowid
+        ],
+    )?;
+    if changed == 0 {
+        return Err(Error::ProjectNotFound(rowid.to_string()));
     }
+    save_tags(tx, rowid, project.tags.as_deref().unwrap_or_default())
 }
 
-pub fn reset_database(config: &Config) -> utils::Result<()> {
-    fs::remove_file(&config.database_path)?;
+/// Replace the tags of the project with `rowid`
+fn save_tags(tx: &Transaction, rowid: i64, tags: &[String]) -> utils::Result<()> {
+    tx.execute("DELETE FROM project_tag WHERE project_rowid = ?", [rowid])?;
+    let mut statement = tx.prepare("INSERT OR IGNORE INTO project_tag (project_rowid, tag) VALUES (?, ?)")?;
+    for tag in tags {
+        statement.execute(params![rowid, tag])?;
+    }
     Ok(())
 }
 
+fn directory_to_sql(project: &Project) -> utils::Result<Option<String>> {
+    Ok(project.directory.as_ref().map(serde_json::to_string).transpose()?)
+}
+
+fn project_from_row(row: &Row) -> rusqlite::Result<Project> {
+    let directory = row
+        .get::<_, Option<String>>(3)?
+        .map(|dir| serde_json::from_str::<Option<PathBuf>>(&dir))
+        .transpose()
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e)))?
+        .flatten();
+
+    Ok(Project {
+        rowid: row.get(0)?,
+        name: row.get(1)?,
+        desc: row.get(2)?,
+        directory,
+        tags: None,
+        language: row.get(4)?,
+        category: row.get(5)?,
+        created_at: row.get(6)?,
+        last_opened_at: row.get(7)?,
+        open_count: row.get(8)?,
+    })
+}
+
+/// Delete the database, taking a snapshot of it first
+///
+/// Returns the path of the snapshot, if there was a database to take one of
+pub fn reset_database(config: &Config) -> utils::Result<Option<PathBuf>> {
+    let snapshot = backup::snapshot(config)?;
+    if snapshot.is_some() {
+        fs::remove_file(&config.database_path)?;
+        // Left behind by the write-ahead log when another process still has the database open
+        for suffix in ["-wal", "-shm"] {
+            let path = format!("{}{suffix}", config.database_path);
+            if Path::new(&path).exists() {
+                fs::remove_file(path)?;
+            }
+        }
+    }
+    Ok(snapshot)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::Config;
+    use crate::store::tests::project;
     use anyhow::Result;
 
     #[test]
@@ -58,6 +382,7 @@ mod tests {
         let config = Config {
             database_path: "./tests/test_files/database.db".to_owned(),
             base_dir: None,
+            ..Config::new()
         };
 
         let _ = std::fs::remove_file(&config.database_path);
@@ -70,13 +395,18 @@ mod tests {
             tags: Some(vec!["test".to_owned(), "project".to_owned()]),
             language: Some("rust".to_owned()),
             category: None,
+            created_at: None,
+            last_opened_at: None,
+            open_count: None,
         };
 
+        let mut db = ProjectDb::open(&config)?;
+
         println!("Adding project");
-        add_project(&config, &project)?;
+        db.add(&project)?;
 
         println!("Loading project");
-        let projects = load_projects(&config)?;
+        let projects = db.load_projects()?;
 
         assert_eq!(projects.len(), 1);
 
@@ -91,4 +421,107 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_separate_databases() -> Result<()> {
+        let mut first = ProjectDb::open_in_memory()?;
+        let mut second = ProjectDb::open_in_memory()?;
+
+        first.add(&project("one", "rust", &["cli"]))?;
+        second.add_projects(&[project("two", "go", &[]), project("three", "rust", &["cli", "web"])])?;
+
+        assert_eq!(first.load_projects()?.len(), 1);
+        assert_eq!(second.load_projects()?.len(), 2);
+
+        let filter = ProjectFilter {
+            tags: vec!["cli".to_owned()],
+            language: Some("RUST".to_owned()),
+            ..ProjectFilter::default()
+        };
+        let found = second.query(&filter)?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name.as_deref(), Some("three"));
+
+        let mut three = second.find_project("three")?;
+        assert_eq!(second.find_project(&three.rowid.unwrap_or_default().to_string())?, three);
+        second.record_open(&mut three)?;
+        assert_eq!(second.find_project("three")?.open_count, Some(1));
+
+        assert_eq!(second.rename_tag("cli", "web")?, 1);
+        assert_eq!(second.tag_counts()?, vec![("web".to_owned(), 1)]);
+
+        second.remove(&three)?;
+        assert!(matches!(second.find_project("three"), Err(Error::ProjectNotFound(_))));
+        assert_eq!(first.find_project("one")?.tags, Some(vec!["cli".to_owned()]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_and_find() -> Result<()> {
+        let mut db = ProjectDb::open_in_memory()?;
+        db.add_projects(&[project("fpm", "rust", &["cli"]), project("site", "go", &[])])?;
+
+        let mut fpm = db.find_project("fpm")?;
+        let rowid = fpm.rowid.unwrap_or_default();
+        fpm.name = Some("fpm-cli".to_owned());
+        fpm.directory = Some(PathBuf::from("/projects/fpm-cli"));
+        fpm.tags = Some(vec!["tool".to_owned(), "rust".to_owned()]);
+        db.update(&fpm)?;
+
+        assert_eq!(db.find_project("fpm-cli")?, fpm);
+        assert_eq!(db.find_project(&rowid.to_string())?, fpm);
+        assert!(matches!(db.find_project("fpm"), Err(Error::ProjectNotFound(_))));
+        assert_eq!(db.find_project("site")?.language.as_deref(), Some("go"));
+
+        // A project with a number as its name is found by the name before anything is looked up by id
+        db.add(&project(&rowid.to_string(), "c", &[]))?;
+        assert_eq!(db.find_project(&rowid.to_string())?.language.as_deref(), Some("c"));
+        db.add(&project("2024", "c", &[]))?;
+        assert_eq!(db.find_project("2024")?.name.as_deref(), Some("2024"));
+        assert!(matches!(db.find_project("99"), Err(Error::ProjectNotFound(_))));
+
+        let missing = Project {
+            rowid: Some(99),
+            ..project("missing", "c", &[])
+        };
+        assert!(matches!(db.update(&missing), Err(Error::ProjectNotFound(_))));
+        assert!(matches!(db.update(&project("new", "c", &[])), Err(Error::Static(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_existing_database() -> Result<()> {
+        let path = PathBuf::from("./tests/test_files/database_migrate.db");
+        let _ = std::fs::remove_file(&path);
+
+        // A database from before tags had their own table
+        {
+            let conn = Connection::open(&path)?;
+            conn.execute(
+                "CREATE TABLE _turbosql_migrations (rowid INTEGER PRIMARY KEY, migration TEXT NOT NULL) STRICT",
+                [],
+            )?;
+            for migration in &MIGRATIONS[..10] {
+                conn.execute(migration, [])?;
+                conn.execute("INSERT INTO _turbosql_migrations (migration) VALUES (?)", [migration])?;
+            }
+            conn.execute(
+                r#"INSERT INTO project (name, directory, tags) VALUES ('old', '"/tmp/old"', '["a","b"]')"#,
+                [],
+            )?;
+        }
+
+        let db = ProjectDb::open_path(&path)?;
+        let old = db.find_project("old")?;
+        assert_eq!(old.directory, Some(PathBuf::from("/tmp/old")));
+        assert_eq!(old.tags, Some(vec!["a".to_owned(), "b".to_owned()]));
+        drop(db);
+
+        // Opening it again does not apply anything twice
+        assert_eq!(ProjectDb::open_path(&path)?.load_projects()?.len(), 1);
+
+        Ok(())
+    }
 }
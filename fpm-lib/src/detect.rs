@@ -0,0 +1,365 @@
+//! Guess the name, description and language of a project from the files in its folder.
+//!
+//! Manifests such as `Cargo.toml` or `package.json` are read first. The description falls back to the first paragraph of
+//! the README, and the language to whichever source file extension makes up the most bytes. Languages are lowercase, the
+//! way they are spelled in the folders of the default layout.
+//!
+//! A project is named after its folder, whether it is added or found by a scan. The manifest can name the package
+//! something else, I.E. `fpm-cli` in `fpm/`, but the folder is where the layout puts the project and what it is known by.
+
+use crate::scan::SKIPPED_DIRS;
+use crate::validate::validate_segment;
+use fs_err as fs;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Stop counting source files after this many, so huge folders stay quick
+const MAX_FILES: usize = 10_000;
+
+/// Languages of source file extensions, used when there is no manifest
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("rs", "rust"),
+    ("py", "python"),
+    ("js", "javascript"),
+    ("mjs", "javascript"),
+    ("jsx", "javascript"),
+    ("ts", "typescript"),
+    ("tsx", "typescript"),
+    ("go", "go"),
+    ("c", "c"),
+    ("h", "c"),
+    ("cpp", "c++"),
+    ("cc", "c++"),
+    ("cxx", "c++"),
+    ("hpp", "c++"),
+    ("cs", "c#"),
+    ("fs", "f#"),
+    ("java", "java"),
+    ("kt", "kotlin"),
+    ("scala", "scala"),
+    ("rb", "ruby"),
+    ("php", "php"),
+    ("swift", "swift"),
+    ("dart", "dart"),
+    ("ex", "elixir"),
+    ("exs", "elixir"),
+    ("hs", "haskell"),
+    ("lua", "lua"),
+    ("zig", "zig"),
+    ("sh", "shell"),
+];
+
+const READMES: [&str; 4] = ["README.md", "README", "README.rst", "README.txt"];
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Detected {
+    /// The folder name, which is the name of the project
+    pub name: Option<String>,
+    /// The name of the package in the manifest, if it is a valid project name
+    pub manifest_name: Option<String>,
+    pub desc: Option<String>,
+    pub language: Option<String>,
+}
+
+/// Inspect `dir` for the details of the project inside it
+pub fn detect(dir: &Path) -> Detected {
+    let manifest = read_manifest(dir).unwrap_or_default();
+
+    Detected {
+        name: dir.file_name().map(|name| name.to_string_lossy().into_owned()),
+        // Manifest names can contain a scope or path, I.E. `@org/name` or `github.com/org/name`
+        manifest_name: manifest
+            .name
+            .as_deref()
+            .and_then(|name| name.rsplit('/').next())
+            .map(str::trim)
+            .filter(|name| validate_segment("name", name).is_ok())
+            .map(str::to_owned),
+        desc: manifest.desc.or_else(|| readme_summary(dir)),
+        language: manifest.language.or_else(|| language_by_extension(dir)),
+    }
+}
+
+/// The first paragraph of the README that is not a heading, badge or HTML
+pub fn readme_summary(dir: &Path) -> Option<String> {
+    let content = READMES.iter().find_map(|name| fs::read_to_string(dir.join(name)).ok())?;
+
+    let mut paragraph = Vec::new();
+    for line in content.lines().map(str::trim) {
+        let skipped = line.starts_with('#')
+            || line.starts_with("![")
+            || line.starts_with("[![")
+            || line.starts_with('<')
+            || line.chars().all(|c| matches!(c, '=' | '-'));
+        if line.is_empty() || skipped {
+            if !paragraph.is_empty() {
+                break;
+            }
+            continue;
+        }
+        paragraph.push(line);
+    }
+
+    Some(paragraph.join(" ")).filter(|summary| !summary.is_empty())
+}
+
+/// The language with the most bytes of source files in `dir`
+pub fn language_by_extension(dir: &Path) -> Option<String> {
+    let mut bytes = HashMap::new();
+    let mut files = 0;
+    count_bytes(dir, &mut bytes, &mut files);
+
+    bytes
+        .into_iter()
+        .max_by(|(a_lang, a), (b_lang, b)| a.cmp(b).then_with(|| b_lang.cmp(a_lang)))
+        .map(|(language, _)| language.to_owned())
+}
+
+fn count_bytes(dir: &Path, bytes: &mut HashMap<&'static str, u64>, files: &mut usize) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        if *files >= MAX_FILES {
+            return;
+        }
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if file_name.starts_with('.') {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            if !SKIPPED_DIRS.contains(&file_name.as_str()) {
+                count_bytes(&entry.path(), bytes, files);
+            }
+        } else if file_type.is_file() {
+            *files += 1;
+            let extension = Path::new(&file_name).extension().and_then(|ext| ext.to_str());
+            let language = extension.and_then(|ext| {
+                EXTENSIONS
+                    .iter()
+                    .find(|(known, _)| known.eq_ignore_ascii_case(ext))
+                    .map(|(_, language)| *language)
+            });
+            if let (Some(language), Ok(metadata)) = (language, entry.metadata()) {
+                *bytes.entry(language).or_default() += metadata.len();
+            }
+        }
+    }
+}
+
+/// The details read from a manifest file
+#[derive(Default)]
+struct Manifest {
+    name: Option<String>,
+    desc: Option<String>,
+    language: Option<String>,
+}
+
+impl Manifest {
+    fn new(language: &str, name: Option<String>, desc: Option<String>) -> Self {
+        Self {
+            name,
+            desc: desc.filter(|desc| !desc.trim().is_empty()),
+            language: Some(language.to_owned()),
+        }
+    }
+}
+
+fn read_manifest(dir: &Path) -> Option<Manifest> {
+    let read = |name: &str| fs::read_to_string(dir.join(name)).ok();
+
+    if let Some(content) = read("Cargo.toml") {
+        let package = toml::from_str::<toml::Table>(&content)
+            .ok()
+            .and_then(|mut t| t.remove("package"));
+        return Some(Manifest::new(
+            "rust",
+            toml_str(package.as_ref(), "name"),
+            toml_str(package.as_ref(), "description"),
+        ));
+    }
+    if let Some(content) = read("package.json") {
+        let package = serde_json::from_str::<serde_json::Value>(&content).ok();
+        let json_str = |key: &str| package.as_ref()?.get(key)?.as_str().map(str::to_owned);
+        let language = if dir.join("tsconfig.json").exists() {
+            "typescript"
+        } else {
+            "javascript"
+        };
+        return Some(Manifest::new(language, json_str("name"), json_str("description")));
+    }
+    if let Some(content) = read("pyproject.toml") {
+        let table = toml::from_str::<toml::Value>(&content).ok();
+        let project = table
+            .as_ref()
+            .and_then(|t| t.get("project").or_else(|| t.get("tool")?.get("poetry")));
+        return Some(Manifest::new(
+            "python",
+            toml_str(project, "name"),
+            toml_str(project, "description"),
+        ));
+    }
+    if dir.join("setup.py").exists() {
+        return Some(Manifest::new("python", None, None));
+    }
+    if let Some(content) = read("go.mod") {
+        let module = content
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("module "))
+            .map(|module| module.trim().to_owned());
+        return Some(Manifest::new("go", module, None));
+    }
+    if let Some(content) = read("composer.json") {
+        let package = serde_json::from_str::<serde_json::Value>(&content).ok();
+        let json_str = |key: &str| package.as_ref()?.get(key)?.as_str().map(str::to_owned);
+        return Some(Manifest::new("php", json_str("name"), json_str("description")));
+    }
+    if let Some(content) = read("pubspec.yaml") {
+        let yaml_str = |key: &str| {
+            content
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+                .map(|value| value.trim().trim_matches(|c| c == '"' || c == '\'').to_owned())
+        };
+        return Some(Manifest::new("dart", yaml_str("name"), yaml_str("description")));
+    }
+    if let Some((path, language)) = find_project_file(dir) {
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned());
+        let desc = fs::read_to_string(&path).ok().and_then(|content| {
+            let start = content.find("<Description>")? + "<Description>".len();
+            let end = content[start..].find("</Description>")? + start;
+            Some(content[start..end].trim().to_owned())
+        });
+        return Some(Manifest::new(language, name, desc));
+    }
+    if let Some(content) = read("CMakeLists.txt") {
+        let name = content.lines().find_map(|line| {
+            let args = line.trim().strip_prefix("project(")?;
+            args.split(|c: char| c.is_whitespace() || c == ')').next().map(str::to_owned)
+        });
+        return Some(Manifest::new("c++", name, None));
+    }
+
+    let language = [
+        ("build.gradle.kts", "kotlin"),
+        ("build.gradle", "java"),
+        ("pom.xml", "java"),
+        ("Gemfile", "ruby"),
+        ("mix.exs", "elixir"),
+        ("Package.swift", "swift"),
+    ]
+    .into_iter()
+    .find(|(marker, _)| dir.join(marker).exists())?
+    .1;
+    Some(Manifest::new(language, None, None))
+}
+
+/// A .NET project file in `dir` and its language
+fn find_project_file(dir: &Path) -> Option<(PathBuf, &'static str)> {
+    let mut entries = fs::read_dir(dir).ok()?.flatten().map(|e| e.path()).collect::<Vec<_>>();
+    entries.sort();
+
+    entries.into_iter().find_map(|path| {
+        let language = match path.extension()?.to_str()? {
+            "csproj" => "c#",
+            "fsproj" => "f#",
+            "vbproj" => "visual basic",
+            _ => return None,
+        };
+        Some((path, language))
+    })
+}
+
+fn toml_str(table: Option<&toml::Value>, key: &str) -> Option<String> {
+    table?.get(key)?.as_str().map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_detect() -> Result<()> {
+        let root = PathBuf::from("./tests/test_files/detect");
+        let _ = std::fs::remove_dir_all(&root);
+
+        let projects = [
+            (
+                "cargo",
+                "Cargo.toml",
+                "[package]\nname = \"fpm\"\ndescription = \"A project manager\"\n",
+            ),
+            ("node", "package.json", r#"{"name": "@org/site", "description": "My site"}"#),
+            ("poetry", "pyproject.toml", "[tool.poetry]\nname = \"tool\"\n"),
+            ("gomod", "go.mod", "module github.com/org/server\n\ngo 1.20\n"),
+            (
+                "dotnet",
+                "App.csproj",
+                "<Project><PropertyGroup><Description>An app</Description></PropertyGroup></Project>",
+            ),
+            (
+                "cmake",
+                "CMakeLists.txt",
+                "cmake_minimum_required(VERSION 3.10)\nproject(engine CXX)\n",
+            ),
+        ];
+        for (dir, file, content) in projects {
+            fs::create_dir_all(root.join(dir))?;
+            fs::write(root.join(dir).join(file), content)?;
+        }
+        fs::write(
+            root.join("poetry").join("README.md"),
+            "# Tool\n\n[![CI](badge.svg)](ci)\n\nDoes things\nquickly.\n\nMore details.\n",
+        )?;
+
+        let detected = |dir: &str| detect(&root.join(dir));
+        let expected = |dir: &str, manifest_name: &str, desc: Option<&str>, language: &str| Detected {
+            name: Some(dir.to_owned()),
+            manifest_name: Some(manifest_name.to_owned()),
+            desc: desc.map(str::to_owned),
+            language: Some(language.to_owned()),
+        };
+        assert_eq!(detected("cargo"), expected("cargo", "fpm", Some("A project manager"), "rust"));
+        assert_eq!(detected("node"), expected("node", "site", Some("My site"), "javascript"));
+        assert_eq!(
+            detected("poetry"),
+            expected("poetry", "tool", Some("Does things quickly."), "python")
+        );
+        assert_eq!(detected("gomod"), expected("gomod", "server", None, "go"));
+        assert_eq!(detected("dotnet"), expected("dotnet", "App", Some("An app"), "c#"));
+        assert_eq!(detected("cmake"), expected("cmake", "engine", None, "c++"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_language_by_extension() -> Result<()> {
+        let root = PathBuf::from("./tests/test_files/detect_extension");
+        let _ = std::fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src"))?;
+        fs::create_dir_all(root.join("node_modules"))?;
+
+        fs::write(root.join("src").join("main.c"), "int main() { return 0; }")?;
+        fs::write(root.join("build.sh"), "make")?;
+        fs::write(root.join("node_modules").join("big.js"), "x".repeat(1000))?;
+
+        assert_eq!(language_by_extension(&root), Some("c".to_owned()));
+        assert_eq!(
+            detect(&root),
+            Detected {
+                name: Some("detect_extension".to_owned()),
+                manifest_name: None,
+                desc: None,
+                language: Some("c".to_owned()),
+            }
+        );
+
+        Ok(())
+    }
+}
@@ -11,7 +11,10 @@ pub enum Error {
     IO(#[from] std::io::Error),
 
     #[error(transparent)]
-    Sql(#[from] turbosql::Error),
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
 
     #[error(transparent)]
     TomlDes(#[from] toml::de::Error),
@@ -21,4 +24,49 @@ pub enum Error {
 
     #[error("config missing needed value for `{0}`")]
     ConfigMissingValue(String),
+
+    #[error("no project found matching `{0}`")]
+    ProjectNotFound(String),
+
+    #[error("`{0}` matches more than one project, use its id instead")]
+    AmbiguousProject(String),
+
+    #[error("no template named `{0}` was found")]
+    TemplateNotFound(String),
+
+    #[error("template group `{0}` includes itself")]
+    TemplateGroupCycle(String),
+
+    #[error("command `{0}` failed: {1}")]
+    CommandFailed(String, String),
+
+    #[error("directory `{}` already exists", .0.display())]
+    DirectoryExists(std::path::PathBuf),
+
+    #[error("invalid {0} `{1}`: {2}")]
+    InvalidName(String, String, String),
+
+    #[error("`{}` is outside of the base directory `{}`", .0.display(), .1.display())]
+    PathOutsideBaseDir(std::path::PathBuf, std::path::PathBuf),
+
+    #[error("project is missing `{0}`, which is needed by the folder layout `{1}`")]
+    LayoutMissingValue(String, String),
+
+    #[error("invalid folder layout `{0}`: {1}")]
+    InvalidLayout(String, String),
+
+    #[error("could not migrate the database: {0}")]
+    Migration(String),
+
+    #[error("`{}` is not a project database", .0.display())]
+    InvalidBackup(std::path::PathBuf),
+
+    #[error(transparent)]
+    TomlEdit(#[from] toml_edit::TomlError),
+
+    #[error(transparent)]
+    Git(#[from] git2::Error),
+
+    #[error("unknown config key `{0}`")]
+    UnknownConfigKey(String),
 }
@@ -0,0 +1,142 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::utils::Result;
+use std::path::Path;
+use std::process::Command;
+
+/// Clone the repository at `url` into `dir` using the configured git command
+pub fn clone(config: &Config, url: &str, dir: &Path) -> Result<()> {
+    // A custom git command may not end its options with `--`, so a url like `--upload-pack=...` is never passed on
+    if url.starts_with('-') {
+        return Err(Error::InvalidName(
+            "url".to_owned(),
+            url.to_owned(),
+            "it starts with `-`".to_owned(),
+        ));
+    }
+    let args = clone_command(config, url, dir);
+    let Some((program, args)) = args.split_first() else {
+        return Err(Error::ConfigMissingValue("git_command".to_owned()));
+    };
+
+    let output = Command::new(program).args(args).output()?;
+    if !output.status.success() {
+        return Err(Error::CommandFailed(
+            config.git_command().to_owned(),
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Build the arguments of the configured git command.
+///
+/// The command is split on whitespace before the placeholders are filled in, so urls and directories containing
+/// spaces are still passed as a single argument.
+pub fn clone_command(config: &Config, url: &str, dir: &Path) -> Vec<String> {
+    let dir = dir.to_string_lossy();
+    config
+        .git_command()
+        .split_whitespace()
+        .map(|arg| arg.replace("{url}", url).replace("{dir}", &dir))
+        .collect()
+}
+
+/// Guess the name of a repository from its url, I.E. `https://github.com/Frazzer951/fpm.git` gives `fpm`
+pub fn repo_name_from_url(url: &str) -> Option<String> {
+    let url = url.trim().trim_end_matches(['/', '\\']);
+    let url = url.strip_suffix(".git").unwrap_or(url);
+
+    let name = url.rsplit(['/', '\\', ':']).next()?;
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use fs_err as fs;
+    use git2::{Repository, Signature};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_clone() -> Result<()> {
+        let root = PathBuf::from("./tests/test_files/git_clone");
+        let _ = std::fs::remove_dir_all(&root);
+        fs::create_dir_all(&root)?;
+        let root = fs::canonicalize(&root)?;
+
+        // A local repository with a single commit to clone from
+        let source = root.join("source");
+        let repo = Repository::init(&source)?;
+        fs::write(source.join("README.md"), "# demo")?;
+        let mut index = repo.index()?;
+        index.add_path(Path::new("README.md"))?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let signature = Signature::now("fpm", "fpm@example.com")?;
+        repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])?;
+
+        let config = Config::new();
+        let url = format!("file://{}", source.display());
+        let dir = root.join("my clone");
+        clone(&config, &url, &dir)?;
+        assert_eq!(fs::read_to_string(dir.join("README.md"))?, "# demo");
+
+        let plain_path = root.join("plain");
+        clone(&config, &source.to_string_lossy(), &plain_path)?;
+        assert!(plain_path.join("README.md").is_file());
+
+        assert!(matches!(
+            clone(&config, "--upload-pack=touch pwned", &root.join("option")),
+            Err(Error::InvalidName(..))
+        ));
+        assert!(!root.join("option").exists());
+
+        // git refuses to clone into a folder that is not empty
+        assert!(matches!(
+            clone(&config, &source.to_string_lossy(), &dir),
+            Err(Error::CommandFailed(..))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_repo_name_from_url() {
+        let cases = [
+            ("https://github.com/Frazzer951/fpm.git", Some("fpm")),
+            ("https://github.com/Frazzer951/fpm", Some("fpm")),
+            ("git@github.com:Frazzer951/fpm.git", Some("fpm")),
+            ("file:///tmp/repos/demo/", Some("demo")),
+            ("/tmp/repos/demo.git", Some("demo")),
+            ("C:\\repos\\demo", Some("demo")),
+            ("", None),
+        ];
+
+        for (url, name) in cases {
+            assert_eq!(repo_name_from_url(url).as_deref(), name, "{url}");
+        }
+    }
+
+    #[test]
+    fn test_clone_command() {
+        let mut config = Config::new();
+        let dir = PathBuf::from("/tmp/my projects/demo");
+
+        assert_eq!(
+            clone_command(&config, "file:///tmp/demo", &dir),
+            vec!["git", "clone", "--", "file:///tmp/demo", "/tmp/my projects/demo"]
+        );
+
+        config.git_command = Some("git clone --depth 1 {url} {dir}".to_owned());
+        assert_eq!(
+            clone_command(&config, "file:///tmp/demo", &dir),
+            vec!["git", "clone", "--depth", "1", "file:///tmp/demo", "/tmp/my projects/demo"]
+        );
+    }
+}
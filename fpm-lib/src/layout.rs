@@ -0,0 +1,270 @@
+//! Where generated project folders are placed inside `base_dir`.
+//!
+//! A layout is a path template such as `{category}/{language}/{name}`. Placeholders are
+//! `name`, `desc`, `language`, `category`, `tags` (all tags joined with `-`) and `tags[N]`.
+//!
+//! - A placeholder ending in `?`, I.E. `{category?}`, may be missing. A path segment that ends
+//!   up empty because of it is left out
+//! - Any other missing placeholder is an error
+//! - Filters are applied in order after a `|`, I.E. `{name|slug}`. The supported filters are
+//!   `lower`, `upper` and `slug`
+//!
+//! The `layouts` table of the config overrides the layout for projects of a category. Every
+//! folder a layout produces is checked with [`crate::validate`]. Scans go the other way with [`match_layout`], reading
+//! the category and language back from the folders a project is in.
+
+use crate::{
+    error::Error,
+    project::Project,
+    utils::Result,
+    validate::{slug, validate_segment},
+};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// The layout used when none is configured, the same as the original hard-coded folder structure
+pub const DEFAULT_LAYOUT: &str = "{category?}/{language?}/{name}";
+
+/// Render `layout` for `project` into a path relative to `base_dir`
+pub fn render_layout(layout: &str, project: &Project) -> Result<PathBuf> {
+    let mut path = PathBuf::new();
+    for segment in layout.split('/') {
+        let rendered = render_segment(segment, layout, project)?;
+        if !rendered.is_empty() {
+            // Values can contain anything, so make sure they did not add or escape a folder
+            validate_segment("folder", &rendered)?;
+            path.push(rendered);
+        }
+    }
+
+    if path.as_os_str().is_empty() {
+        return Err(Error::InvalidLayout(
+            layout.to_owned(),
+            "it produced an empty path".to_owned(),
+        ));
+    }
+    Ok(path)
+}
+
+fn render_segment(segment: &str, layout: &str, project: &Project) -> Result<String> {
+    let invalid = |reason: String| Error::InvalidLayout(layout.to_owned(), reason);
+
+    let mut rendered = String::new();
+    let mut rest = segment;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            return Err(invalid("a `{` is never closed".to_owned()));
+        };
+
+        let mut parts = rest[start + 1..end].split('|').map(str::trim);
+        let placeholder = parts.next().unwrap_or_default();
+        let (key, optional) = match placeholder.strip_suffix('?') {
+            Some(key) => (key, true),
+            None => (placeholder, false),
+        };
+
+        let value = match lookup(key, project) {
+            Some(value) => Some(value),
+            None if is_placeholder(key) => None,
+            None => return Err(invalid(format!("unknown placeholder `{key}`"))),
+        };
+        let value = match value.filter(|value| !value.is_empty()) {
+            Some(mut value) => {
+                for filter in parts {
+                    value = apply_filter(filter, &value).ok_or_else(|| invalid(format!("unknown filter `{filter}`")))?;
+                }
+                Some(value)
+            },
+            None => None,
+        };
+        // A filter can empty a value, I.E. `slug` of `!!!`, which counts as missing
+        match value.filter(|value| !value.is_empty()) {
+            Some(value) => rendered.push_str(&value),
+            None if optional => {},
+            None => return Err(Error::LayoutMissingValue(key.to_owned(), layout.to_owned())),
+        }
+
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+
+    Ok(rendered)
+}
+
+/// Every way the folders `parts`, relative to `base_dir`, could have been rendered from `layout`.
+///
+/// Each match holds the values of the placeholders that make up a folder on their own or with fixed text around them,
+/// I.E. `category` for `{category}` or `v{language?}`. Folders with several placeholders match anything.
+pub fn match_layout(layout: &str, parts: &[&str]) -> Vec<BTreeMap<String, String>> {
+    let segments = layout.split('/').collect::<Vec<_>>();
+    let mut matches = vec![];
+    match_segments(&segments, parts, BTreeMap::new(), &mut matches);
+    matches
+}
+
+fn match_segments(
+    segments: &[&str],
+    parts: &[&str],
+    mut values: BTreeMap<String, String>,
+    matches: &mut Vec<BTreeMap<String, String>>,
+) {
+    let Some((segment, segments)) = segments.split_first() else {
+        if parts.is_empty() {
+            matches.push(values);
+        }
+        return;
+    };
+    if is_optional_segment(segment) {
+        match_segments(segments, parts, values.clone(), matches);
+    }
+    let Some((part, parts)) = parts.split_first() else {
+        return;
+    };
+
+    match segment.matches('{').count() {
+        0 if segment != part => return,
+        1 => {
+            let Some((prefix, rest)) = segment.split_once('{') else {
+                return;
+            };
+            let Some((placeholder, suffix)) = rest.split_once('}') else {
+                return;
+            };
+            let Some(value) = part
+                .strip_prefix(prefix)
+                .and_then(|value| value.strip_suffix(suffix))
+                .filter(|value| !value.is_empty())
+            else {
+                return;
+            };
+            let key = placeholder.split('|').next().unwrap_or_default().trim().trim_end_matches('?');
+            values.insert(key.to_owned(), value.to_owned());
+        },
+        _ => {},
+    }
+    match_segments(segments, parts, values, matches);
+}
+
+/// Whether `segment` is made of optional placeholders only, so it is left out when they are all missing
+fn is_optional_segment(segment: &str) -> bool {
+    let mut rest = segment;
+    while let Some(placeholder) = rest.strip_prefix('{') {
+        let Some((placeholder, next)) = placeholder.split_once('}') else {
+            return false;
+        };
+        if !placeholder.split('|').next().unwrap_or_default().trim().ends_with('?') {
+            return false;
+        }
+        rest = next;
+    }
+    rest.is_empty() && segment.starts_with('{')
+}
+
+fn lookup(key: &str, project: &Project) -> Option<String> {
+    let tags = project.tags.as_deref().unwrap_or_default();
+    match key {
+        "name" => project.name.clone(),
+        "desc" => project.desc.clone(),
+        "language" => project.language.clone(),
+        "category" => project.category.clone(),
+        "tags" => Some(tags.join("-")),
+        _ => tag_index(key).and_then(|i| tags.get(i).cloned()),
+    }
+}
+
+fn is_placeholder(key: &str) -> bool {
+    matches!(key, "name" | "desc" | "language" | "category" | "tags") || tag_index(key).is_some()
+}
+
+/// The index of a `tags[N]` placeholder
+fn tag_index(key: &str) -> Option<usize> {
+    key.strip_prefix("tags[")?.strip_suffix(']')?.parse().ok()
+}
+
+fn apply_filter(filter: &str, value: &str) -> Option<String> {
+    match filter {
+        "lower" => Some(value.to_lowercase()),
+        "upper" => Some(value.to_uppercase()),
+        "slug" => Some(slug(value)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_render_layout() -> Result<()> {
+        let project = Project {
+            name: Some("My Project".to_owned()),
+            language: Some("Rust".to_owned()),
+            tags: Some(vec!["work".to_owned(), "cli".to_owned()]),
+            ..Project::default()
+        };
+
+        assert_eq!(
+            render_layout(DEFAULT_LAYOUT, &project)?,
+            PathBuf::from("Rust").join("My Project")
+        );
+        assert_eq!(
+            render_layout("{tags[0]}/{language|lower}/{name|slug}", &project)?,
+            PathBuf::from("work").join("rust").join("my-project")
+        );
+        assert_eq!(
+            render_layout("{tags[5]?}/{tags}-{name|upper}", &project)?,
+            PathBuf::from("work-cli-MY PROJECT")
+        );
+
+        assert!(matches!(
+            render_layout("{category}/{name}", &project),
+            Err(Error::LayoutMissingValue(key, _)) if key == "category"
+        ));
+        let symbols = Project {
+            name: Some("!!!".to_owned()),
+            ..Project::default()
+        };
+        assert!(matches!(
+            render_layout("{language?}/{name|slug}", &symbols),
+            Err(Error::LayoutMissingValue(key, _)) if key == "name"
+        ));
+        assert!(matches!(
+            render_layout("{owner}/{name}", &project),
+            Err(Error::InvalidLayout(..))
+        ));
+        assert!(matches!(
+            render_layout("{name|title}", &project),
+            Err(Error::InvalidLayout(..))
+        ));
+        assert!(matches!(render_layout("{name", &project), Err(Error::InvalidLayout(..))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_layout() {
+        let values = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|&(key, value)| (key.to_owned(), value.to_owned()))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        assert_eq!(
+            match_layout(DEFAULT_LAYOUT, &["work", "rust", "api"]),
+            vec![values(&[("category", "work"), ("language", "rust"), ("name", "api")])]
+        );
+        // Either of the optional folders could be the one that is there
+        assert_eq!(match_layout(DEFAULT_LAYOUT, &["work", "api"]).len(), 2);
+        assert_eq!(match_layout(DEFAULT_LAYOUT, &["api"]), vec![values(&[("name", "api")])]);
+        assert!(match_layout(DEFAULT_LAYOUT, &["a", "b", "c", "d"]).is_empty());
+
+        assert_eq!(
+            match_layout("code/v{language|lower}/{tags}-{name}", &["code", "vgo", "cli-tool"]),
+            vec![values(&[("language", "go")])]
+        );
+        assert!(match_layout("code/{name}", &["src", "tool"]).is_empty());
+    }
+}
@@ -1,10 +1,22 @@
 #![warn(clippy::unwrap_used, clippy::expect_used)]
 
-// Must be included first to not cause compile error
-pub mod project;
-
+pub mod backup;
 pub mod config;
 pub mod database;
+pub mod detect;
 pub mod error;
+pub mod git;
+pub mod layout;
+pub mod open;
+pub mod project;
+pub mod registry;
+pub mod scan;
+pub mod search;
+pub mod settings;
+pub mod status;
+pub mod store;
+pub mod template;
+pub mod validate;
+pub mod verify;
 
 mod utils;
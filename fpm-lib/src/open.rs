@@ -0,0 +1,123 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::project::Project;
+use crate::utils::Result;
+use std::path::Path;
+use std::process::Command;
+
+/// Pick the command template used to open `project`.
+///
+/// In order of precedence this is the `open` entry for the project's category, the `open` entry for its language,
+/// the configured `editor`, and finally the `VISUAL` and `EDITOR` environment variables.
+pub fn open_command(config: &Config, project: &Project) -> Option<String> {
+    open_command_with_env(config, project, |key| std::env::var(key).ok())
+}
+
+fn open_command_with_env(config: &Config, project: &Project, env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let overrides = [&project.category, &project.language]
+        .into_iter()
+        .flatten()
+        .filter_map(|key| config.open.get(key).cloned());
+
+    overrides
+        .chain(config.editor.clone())
+        .chain(env("VISUAL"))
+        .chain(env("EDITOR"))
+        .find(|command| !command.trim().is_empty())
+}
+
+/// Build the arguments of an open command.
+///
+/// `{dir}` is replaced with the project directory, if the command does not contain it the directory is passed as the
+/// last argument instead, so a plain `EDITOR=vim` works.
+pub fn open_args(command: &str, dir: &Path) -> Vec<String> {
+    let dir = dir.to_string_lossy();
+    let mut args = command
+        .split_whitespace()
+        .map(|arg| arg.replace("{dir}", &dir))
+        .collect::<Vec<_>>();
+
+    if !command.contains("{dir}") {
+        args.push(dir.into_owned());
+    }
+
+    args
+}
+
+/// Launch the open command for `project` and wait for it to exit
+pub fn open_project(config: &Config, project: &Project) -> Result<()> {
+    let Some(command) = open_command(config, project) else {
+        return Err(Error::ConfigMissingValue("editor".to_owned()));
+    };
+    let Some(dir) = &project.directory else {
+        return Err(Error::Static("project does not have a directory to open"));
+    };
+
+    let args = open_args(&command, dir);
+    let Some((program, args)) = args.split_first() else {
+        return Err(Error::ConfigMissingValue("editor".to_owned()));
+    };
+
+    let status = Command::new(program).args(args).current_dir(dir).status()?;
+    if !status.success() {
+        return Err(Error::CommandFailed(command, status.to_string()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_open_command() {
+        let mut config = Config::new();
+        let mut project = Project::new(
+            Some("fpm".to_owned()),
+            None,
+            vec![],
+            Some("rust".to_owned()),
+            Some("work".to_owned()),
+        );
+        let no_env = |_: &str| None;
+        let env = |key: &str| match key {
+            "VISUAL" => Some(String::new()),
+            "EDITOR" => Some("vim".to_owned()),
+            _ => None,
+        };
+
+        assert_eq!(open_command_with_env(&config, &project, no_env), None);
+        assert_eq!(open_command_with_env(&config, &project, env).as_deref(), Some("vim"));
+
+        config.editor = Some("code {dir}".to_owned());
+        assert_eq!(open_command_with_env(&config, &project, env).as_deref(), Some("code {dir}"));
+
+        config.open.insert("rust".to_owned(), "rustrover {dir}".to_owned());
+        assert_eq!(
+            open_command_with_env(&config, &project, env).as_deref(),
+            Some("rustrover {dir}")
+        );
+
+        config.open.insert("work".to_owned(), "work-ide {dir}".to_owned());
+        assert_eq!(
+            open_command_with_env(&config, &project, env).as_deref(),
+            Some("work-ide {dir}")
+        );
+
+        project.category = None;
+        assert_eq!(
+            open_command_with_env(&config, &project, env).as_deref(),
+            Some("rustrover {dir}")
+        );
+    }
+
+    #[test]
+    fn test_open_args() {
+        let dir = PathBuf::from("/home/me/my project");
+
+        assert_eq!(open_args("code -n {dir}", &dir), vec!["code", "-n", "/home/me/my project"]);
+        assert_eq!(open_args("vim", &dir), vec!["vim", "/home/me/my project"]);
+    }
+}
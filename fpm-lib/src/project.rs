@@ -1,17 +1,26 @@
 use crate::config::Config;
+use crate::git;
+use crate::template::Template;
+use crate::validate::validate_project;
 use fs_err as fs;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use turbosql::Turbosql;
 
-#[derive(Turbosql, Default, Debug, PartialEq, Eq, Clone)]
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone)]
 pub struct Project {
     pub rowid: Option<i64>,
     pub name: Option<String>,
     pub desc: Option<String>,
     pub directory: Option<PathBuf>,
+    /// Stored in the `project_tag` table, the `tags` column of the `project` table is no longer used
     pub tags: Option<Vec<String>>,
     pub language: Option<String>,
     pub category: Option<String>,
+    /// Unix timestamp of when the project was registered
+    pub created_at: Option<i64>,
+    /// Unix timestamp of the last time the project was opened
+    pub last_opened_at: Option<i64>,
+    pub open_count: Option<i64>,
 }
 
 impl Project {
@@ -30,21 +39,45 @@ impl Project {
             tags: Some(tags),
             language,
             category,
+            created_at: Some(crate::utils::now()),
+            last_opened_at: None,
+            open_count: None,
         }
     }
 
-    pub fn build(&mut self, dir: Option<PathBuf>, config: &Config) -> crate::utils::Result<()> {
-        let dir = if let Some(dir) = dir {
-            self.directory = Some(dir.clone());
-            dir
-        } else {
-            let dir = config.gen_project_folder(self)?;
-            self.directory = Some(dir.clone());
-            dir
-        };
+    /// Create the project folder and populate it with `templates`, see [`crate::template`] for how they are layered
+    pub fn build(&mut self, dir: Option<PathBuf>, templates: &[Template], config: &Config) -> crate::utils::Result<()> {
+        validate_project(self)?;
+        let dir = self.set_directory(dir, config)?;
+
+        fs::create_dir_all(&dir)?;
 
-        fs::create_dir_all(dir)?;
+        // Templates skip files that exist, so the last one is applied first for its files to win
+        for template in templates.iter().rev() {
+            template.apply(self, &dir)?;
+        }
 
         Ok(())
     }
+
+    /// Create the project folder by cloning the git repository at `url` into it
+    pub fn build_from_git(&mut self, url: &str, dir: Option<PathBuf>, config: &Config) -> crate::utils::Result<()> {
+        validate_project(self)?;
+        let dir = self.set_directory(dir, config)?;
+
+        if let Some(parent) = dir.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        git::clone(config, url, &dir)
+    }
+
+    fn set_directory(&mut self, dir: Option<PathBuf>, config: &Config) -> crate::utils::Result<PathBuf> {
+        let dir = match dir {
+            Some(dir) => dir,
+            None => config.gen_project_folder(self)?,
+        };
+        self.directory = Some(dir.clone());
+        Ok(dir)
+    }
 }
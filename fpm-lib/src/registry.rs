@@ -0,0 +1,367 @@
+//! A portable copy of the project list.
+//!
+//! Directories inside `base_dir` are exported relative to it and joined onto the importing machine's `base_dir`, so a
+//! registry can be moved between machines with different home folders. Usage statistics are not exported.
+
+use crate::{
+    config::Config,
+    error::Error,
+    project::Project,
+    store::ProjectStore,
+    utils,
+    validate::{ensure_within, validate_project},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+pub const REGISTRY_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Registry {
+    pub version: u32,
+    #[serde(default)]
+    pub projects: Vec<RegistryProject>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone)]
+pub struct RegistryProject {
+    pub name: Option<String>,
+    pub desc: Option<String>,
+    /// Relative to `base_dir` when the project is inside it, otherwise absolute
+    pub directory: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub language: Option<String>,
+    pub category: Option<String>,
+    pub created_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryFormat {
+    Json,
+    Toml,
+}
+
+impl RegistryFormat {
+    /// The format matching the extension of `path`, JSON unless it ends in `.toml`
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Self::Toml,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// What to do with an imported project that has the same name as an existing one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Keep the existing project
+    Skip,
+    /// Replace the details of the existing project, keeping its usage statistics
+    Overwrite,
+    /// Add the imported project with a numbered suffix, I.E. `name-2`
+    Rename,
+}
+
+#[derive(Serialize, Default, Debug, PartialEq, Eq)]
+pub struct ImportReport {
+    pub added: Vec<String>,
+    pub overwritten: Vec<String>,
+    /// The original and new names of the renamed projects
+    pub renamed: Vec<(String, String)>,
+    pub skipped: Vec<String>,
+}
+
+/// The changes an import makes to the database
+#[derive(Default, Debug)]
+pub struct ImportPlan {
+    pub add: Vec<Project>,
+    pub update: Vec<Project>,
+    pub report: ImportReport,
+}
+
+impl Registry {
+    pub fn new(config: &Config, projects: &[Project]) -> Self {
+        let base_dir = config.base_dir.as_ref().map(PathBuf::from);
+        let projects = projects
+            .iter()
+            .map(|project| RegistryProject {
+                name: project.name.clone(),
+                desc: project.desc.clone(),
+                directory: project
+                    .directory
+                    .as_ref()
+                    .map(|dir| export_directory(dir, base_dir.as_deref())),
+                tags: project.tags.clone().unwrap_or_default(),
+                language: project.language.clone(),
+                category: project.category.clone(),
+                created_at: project.created_at,
+            })
+            .collect();
+
+        Self {
+            version: REGISTRY_VERSION,
+            projects,
+        }
+    }
+
+    pub fn parse(content: &str, format: RegistryFormat) -> utils::Result<Self> {
+        let registry: Self = match format {
+            RegistryFormat::Json => serde_json::from_str(content)?,
+            RegistryFormat::Toml => toml::from_str(content)?,
+        };
+        if registry.version > REGISTRY_VERSION {
+            return Err(Error::Generic(format!(
+                "registry version {} is newer than the supported version {REGISTRY_VERSION}",
+                registry.version
+            )));
+        }
+        Ok(registry)
+    }
+
+    pub fn to_string(&self, format: RegistryFormat) -> utils::Result<String> {
+        match format {
+            RegistryFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            RegistryFormat::Toml => Ok(toml::to_string(self)?),
+        }
+    }
+}
+
+/// Export every project in the database
+pub fn export_registry(config: &Config, store: &dyn ProjectStore) -> utils::Result<Registry> {
+    Ok(Registry::new(config, &store.load_projects()?))
+}
+
+/// Work out which projects an import adds and updates without touching the database
+pub fn plan_import(
+    config: &Config,
+    registry: &Registry,
+    existing: &[Project],
+    strategy: MergeStrategy,
+) -> utils::Result<ImportPlan> {
+    let mut plan = ImportPlan::default();
+    let mut taken = existing.iter().filter_map(|p| p.name.clone()).collect::<HashSet<_>>();
+
+    for entry in &registry.projects {
+        let mut project = Project {
+            name: entry.name.clone(),
+            desc: entry.desc.clone(),
+            directory: entry
+                .directory
+                .as_deref()
+                .map(|dir| import_directory(dir, config))
+                .transpose()?,
+            tags: Some(entry.tags.clone()),
+            language: entry.language.clone(),
+            category: entry.category.clone(),
+            created_at: entry.created_at.or_else(|| Some(utils::now())),
+            ..Project::default()
+        };
+        validate_project(&project)?;
+        let name = project.name.clone().unwrap_or_default();
+
+        if !taken.contains(&name) {
+            if project.name.is_some() {
+                taken.insert(name.clone());
+            }
+            plan.report.added.push(name);
+            plan.add.push(project);
+            continue;
+        }
+
+        match strategy {
+            MergeStrategy::Skip => plan.report.skipped.push(name),
+            MergeStrategy::Overwrite => match existing.iter().find(|p| p.name.as_ref() == Some(&name)) {
+                Some(current) => {
+                    project.rowid = current.rowid;
+                    project.created_at = current.created_at.or(project.created_at);
+                    project.last_opened_at = current.last_opened_at;
+                    project.open_count = current.open_count;
+                    plan.report.overwritten.push(name);
+                    plan.update.push(project);
+                },
+                // The name was taken by an earlier project in the same registry
+                None => plan.report.skipped.push(name),
+            },
+            MergeStrategy::Rename => {
+                let new_name = (2..)
+                    .map(|n| format!("{name}-{n}"))
+                    .find(|candidate| !taken.contains(candidate))
+                    .unwrap_or_default();
+                taken.insert(new_name.clone());
+                project.name = Some(new_name.clone());
+                plan.report.renamed.push((name, new_name));
+                plan.add.push(project);
+            },
+        }
+    }
+
+    Ok(plan)
+}
+
+/// Import `registry` into the database, resolving name conflicts with `strategy`
+pub fn import_registry(
+    config: &Config,
+    store: &mut dyn ProjectStore,
+    registry: &Registry,
+    strategy: MergeStrategy,
+) -> utils::Result<ImportReport> {
+    let existing = store.load_projects()?;
+    let plan = plan_import(config, registry, &existing, strategy)?;
+    apply_import(store, &plan)?;
+    Ok(plan.report)
+}
+
+/// Write the changes of a planned import to the database, either all of them or none
+pub fn apply_import(store: &mut dyn ProjectStore, plan: &ImportPlan) -> utils::Result<()> {
+    store.apply_changes(&plan.add, &plan.update)
+}
+
+fn export_directory(dir: &Path, base_dir: Option<&Path>) -> String {
+    let relative = base_dir.and_then(|base| dir.strip_prefix(base).ok());
+    match relative {
+        // Always use `/` so the registry can be read on any platform
+        Some(relative) => relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        None => dir.display().to_string(),
+    }
+}
+
+fn import_directory(dir: &str, config: &Config) -> utils::Result<PathBuf> {
+    let path = PathBuf::from(dir);
+    if path.is_absolute() {
+        return Ok(path);
+    }
+    // Not absolute on this platform, but joining it onto `base_dir` would not give the folder either
+    if is_windows_absolute(dir) {
+        return Err(Error::InvalidName(
+            "directory".to_owned(),
+            dir.to_owned(),
+            "it is an absolute path from another platform".to_owned(),
+        ));
+    }
+
+    match &config.base_dir {
+        Some(base_dir) => ensure_within(Path::new(base_dir), &dir.split('/').collect::<PathBuf>()),
+        None => Err(Error::ConfigMissingValue("base_dir".to_owned())),
+    }
+}
+
+/// `C:\dev`, `C:/dev` or `\\server\share`
+fn is_windows_absolute(dir: &str) -> bool {
+    let bytes = dir.as_bytes();
+    let drive = bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && matches!(bytes[2], b'\\' | b'/');
+    drive || dir.starts_with(r"\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn project(rowid: i64, name: &str, directory: &str) -> Project {
+        Project {
+            rowid: Some(rowid),
+            name: Some(name.to_owned()),
+            directory: Some(PathBuf::from(directory)),
+            tags: Some(vec!["tag".to_owned()]),
+            open_count: Some(3),
+            ..Project::default()
+        }
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let source = Config {
+            base_dir: Some("/home/a/projects".to_owned()),
+            ..Config::new()
+        };
+        let target = Config {
+            base_dir: Some("/home/b/code".to_owned()),
+            ..Config::new()
+        };
+        let projects = vec![
+            project(1, "inside", "/home/a/projects/rust/inside"),
+            project(2, "outside", "/opt/outside"),
+        ];
+
+        let registry = Registry::new(&source, &projects);
+        assert_eq!(registry.projects[0].directory.as_deref(), Some("rust/inside"));
+        assert_eq!(registry.projects[1].directory.as_deref(), Some("/opt/outside"));
+
+        for format in [RegistryFormat::Json, RegistryFormat::Toml] {
+            let parsed = Registry::parse(&registry.to_string(format)?, format)?;
+            assert_eq!(parsed, registry);
+        }
+
+        let plan = plan_import(&target, &registry, &[], MergeStrategy::Skip)?;
+        assert_eq!(
+            plan.add[0].directory,
+            Some(PathBuf::from("/home/b/code").join("rust").join("inside"))
+        );
+        assert_eq!(plan.add[1].directory, Some(PathBuf::from("/opt/outside")));
+        assert_eq!(plan.add[0].open_count, None);
+
+        assert!(matches!(
+            plan_import(&Config::new(), &registry, &[], MergeStrategy::Skip),
+            Err(Error::ConfigMissingValue(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_directory() -> Result<()> {
+        let config = Config {
+            base_dir: Some("/projects".to_owned()),
+            ..Config::new()
+        };
+
+        assert_eq!(import_directory("rust/fpm", &config)?, PathBuf::from("/projects/rust/fpm"));
+        assert_eq!(import_directory("rust/../fpm", &config)?, PathBuf::from("/projects/fpm"));
+        assert!(matches!(
+            import_directory("../../etc", &config),
+            Err(Error::PathOutsideBaseDir(_, _))
+        ));
+        for dir in [r"C:\dev\fpm", "C:/dev/fpm", r"\\server\share\fpm"] {
+            assert!(matches!(import_directory(dir, &config), Err(Error::InvalidName(..))), "{dir}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_strategies() -> Result<()> {
+        let config = Config {
+            base_dir: Some("/projects".to_owned()),
+            ..Config::new()
+        };
+        let existing = vec![project(1, "one", "/projects/one"), project(2, "one-2", "/projects/one-2")];
+        let registry = Registry::new(
+            &config,
+            &[project(5, "one", "/projects/new-one"), project(6, "two", "/projects/two")],
+        );
+
+        let plan = plan_import(&config, &registry, &existing, MergeStrategy::Skip)?;
+        assert_eq!(plan.report.added, vec!["two"]);
+        assert_eq!(plan.report.skipped, vec!["one"]);
+        assert!(plan.update.is_empty());
+
+        let plan = plan_import(&config, &registry, &existing, MergeStrategy::Overwrite)?;
+        assert_eq!(plan.report.overwritten, vec!["one"]);
+        assert_eq!(plan.update[0].rowid, Some(1));
+        assert_eq!(plan.update[0].open_count, Some(3));
+        assert_eq!(plan.update[0].directory, Some(PathBuf::from("/projects/new-one")));
+
+        let plan = plan_import(&config, &registry, &existing, MergeStrategy::Rename)?;
+        assert_eq!(plan.report.renamed, vec![("one".to_owned(), "one-3".to_owned())]);
+        assert_eq!(plan.add.len(), 2);
+        assert_eq!(plan.add[0].rowid, None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+use crate::config::Config;
+use crate::detect::detect;
+use crate::error::Error;
+use crate::layout::{match_layout, DEFAULT_LAYOUT};
+use crate::project::Project;
+use crate::utils::Result;
+use crate::validate::validate_project;
+use fs_err as fs;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+/// Files or folders that mark the root of a project
+pub const PROJECT_MARKERS: &[&str] = &[
+    ".git",
+    ".hg",
+    ".svn",
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "setup.py",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "CMakeLists.txt",
+    "Gemfile",
+    "composer.json",
+    "mix.exs",
+    "pubspec.yaml",
+    "Package.swift",
+];
+
+/// Folders that never contain projects worth registering
+pub(crate) const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "vendor", "venv", "__pycache__"];
+
+/// The new projects a [`scan`] found
+#[derive(Debug, Default)]
+pub struct Scanned {
+    pub projects: Vec<Project>,
+    /// Project roots that were left out because their folder does not make a valid project, with the reason
+    pub invalid: Vec<(PathBuf, Error)>,
+}
+
+/// Walk `root` looking for project roots that are not already in `existing`.
+///
+/// Folders below a project root are not searched, so nested packages of a project are not picked up on their own.
+/// The category and language are filled in from the folders the project is in when they fit the configured layout.
+pub fn scan(root: &Path, config: &Config, existing: &[Project]) -> Result<Scanned> {
+    let known = existing
+        .iter()
+        .filter_map(|project| project.directory.as_deref())
+        .map(normalize)
+        .collect::<HashSet<_>>();
+
+    // Only the root has to be readable, folders below it that can not be read are skipped
+    fs::read_dir(root)?;
+    let mut roots = vec![];
+    find_project_roots(root, &mut roots);
+
+    let mut scanned = Scanned::default();
+    for dir in roots.into_iter().filter(|dir| !known.contains(&normalize(dir))) {
+        match project_from_path(config, dir.clone()) {
+            Ok(project) => scanned.projects.push(project),
+            Err(err) => scanned.invalid.push((dir, err)),
+        }
+    }
+
+    Ok(scanned)
+}
+
+/// Build a project for `dir` named after its folder the way [`detect`] names it, using the folder layout to fill in the
+/// category and language where possible. The description and any language the layout did not give are detected from
+/// the folder contents.
+/// Fails when the folder name is not a valid project name.
+pub fn project_from_path(config: &Config, dir: PathBuf) -> Result<Project> {
+    let detected = detect(&dir);
+    let (category, language) = infer_category_language(config, &dir).unwrap_or_default();
+    let mut project = Project::new(detected.name, detected.desc, vec![], language.or(detected.language), category);
+    validate_project(&project)?;
+    project.directory = Some(dir);
+    Ok(project)
+}
+
+/// Read the category and language back from the folders between `base_dir` and `dir` with the configured layouts.
+/// Nothing is filled in when the folders do not fit a layout, or fit in more than one way.
+fn infer_category_language(config: &Config, dir: &Path) -> Option<(Option<String>, Option<String>)> {
+    let base_dir = normalize(Path::new(config.base_dir.as_ref()?));
+    let dir = normalize(dir);
+    let relative = dir.strip_prefix(base_dir).ok()?;
+
+    let parts = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let parts = parts.iter().map(String::as_str).collect::<Vec<_>>();
+    let language = |values: &BTreeMap<String, String>| values.get("language").map(|language| language.to_lowercase());
+
+    let default_layout = config.layout.as_deref().unwrap_or(DEFAULT_LAYOUT);
+    let mut found = match_layout(default_layout, &parts)
+        .into_iter()
+        // A category with its own layout would not have been placed with the default one
+        .filter(|values| values.get("category").is_none_or(|category| !config.layouts.contains_key(category)))
+        .map(|values| (values.get("category").cloned(), language(&values)))
+        .collect::<Vec<_>>();
+    for (category, layout) in &config.layouts {
+        found.extend(
+            match_layout(layout, &parts)
+                .into_iter()
+                .filter(|values| values.get("category").is_none_or(|value| value == category))
+                .map(|values| (Some(category.clone()), language(&values))),
+        );
+    }
+
+    found.sort();
+    found.dedup();
+    match found.as_slice() {
+        [only] => Some(only.clone()),
+        _ => None,
+    }
+}
+
+fn find_project_roots(dir: &Path, roots: &mut Vec<PathBuf>) {
+    if PROJECT_MARKERS.iter().any(|marker| dir.join(marker).exists()) {
+        roots.push(dir.to_path_buf());
+        return;
+    }
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries = entries.flatten().collect::<Vec<_>>();
+    entries.sort_by_key(fs::DirEntry::file_name);
+
+    for entry in entries {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+        if !is_dir || file_name.starts_with('.') || SKIPPED_DIRS.contains(&file_name.as_str()) {
+            continue;
+        }
+        find_project_roots(&entry.path(), roots);
+    }
+}
+
+/// Make paths comparable, using the canonical path when it exists
+fn normalize(path: &Path) -> PathBuf {
+    if let Ok(path) = fs::canonicalize(path) {
+        return path;
+    }
+    path.components().filter(|c| !matches!(c, Component::CurDir)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_scan() -> Result<()> {
+        let root = PathBuf::from("./tests/test_files/scan");
+        let _ = std::fs::remove_dir_all(&root);
+
+        for (dir, marker) in [
+            ("work/Rust/api", "Cargo.toml"),
+            ("work/Rust/api/crates/inner", "Cargo.toml"),
+            ("personal/site", "package.json"),
+            ("personal/dotfiles", ".git"),
+            ("personal/site/node_modules/dep", "package.json"),
+            ("notes", "todo.txt"),
+            ("personal/draft.", ".git"),
+        ] {
+            fs::create_dir_all(root.join(dir))?;
+            fs::write(root.join(dir).join(marker), "")?;
+        }
+
+        fs::write(root.join("work/Rust/api/Cargo.toml"), "[package]\nname = \"api-server\"\n")?;
+
+        // A folder that can not be read is skipped instead of ending the scan
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::create_dir_all(root.join("locked"))?;
+            fs::set_permissions(root.join("locked"), std::fs::Permissions::from_mode(0o000))?;
+        }
+
+        let config = Config {
+            base_dir: Some(root.to_string_lossy().into_owned()),
+            ..Config::new()
+        };
+        let mut existing = Project::new(Some("dotfiles".to_owned()), None, vec![], None, None);
+        existing.directory = Some(root.join("personal").join("dotfiles"));
+
+        let found = scan(&root, &config, &[existing])?;
+
+        assert_eq!(found.invalid.len(), 1);
+        assert!(matches!(&found.invalid[0], (dir, Error::InvalidName(..)) if dir.ends_with("draft.")));
+        let found = found
+            .projects
+            .iter()
+            .map(|p| (p.name.as_deref(), p.category.as_deref(), p.language.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                (Some("site"), None, Some("javascript")),
+                (Some("api"), Some("work"), Some("rust"))
+            ]
+        );
+        assert!(scan(&root.join("missing"), &config, &[]).is_err());
+
+        // Other layouts are read back the same way
+        let config = Config {
+            base_dir: Some(root.to_string_lossy().into_owned()),
+            layout: Some("{category}/{name}".to_owned()),
+            layouts: [("personal".to_owned(), "personal/{language}/{name}".to_owned())].into(),
+            ..Config::new()
+        };
+        for dir in ["misc/tool", "personal/Go/server"] {
+            fs::create_dir_all(root.join(dir))?;
+        }
+        let infer = |dir: &str| infer_category_language(&config, &root.join(dir));
+        assert_eq!(infer("misc/tool"), Some((Some("misc".to_owned()), None)));
+        assert_eq!(
+            infer("personal/Go/server"),
+            Some((Some("personal".to_owned()), Some("go".to_owned())))
+        );
+        // `personal` has its own layout, which this does not fit
+        assert_eq!(infer("personal/site"), None);
+        assert_eq!(infer("work/Rust/api"), None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use crate::project::Project;
+use crate::utils;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+
+/// A project that matched a fuzzy query, higher scores are better matches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match<'a> {
+    pub score: i64,
+    pub project: &'a Project,
+}
+
+const HOUR: i64 = 60 * 60;
+const DAY: i64 = 24 * HOUR;
+const WEEK: i64 = 7 * DAY;
+
+/// How frequently and recently a project has been opened, the same way zoxide ranks directories.
+///
+/// The number of times a project was opened is weighted by how long ago it was last opened.
+pub fn frecency(project: &Project) -> f64 {
+    frecency_at(project, utils::now())
+}
+
+fn frecency_at(project: &Project, now: i64) -> f64 {
+    let Some(last_opened) = project.last_opened_at else {
+        return 0.0;
+    };
+    #[allow(clippy::cast_precision_loss)]
+    let count = project.open_count.unwrap_or_default() as f64;
+
+    let age = now - last_opened;
+    let weight = if age < HOUR {
+        4.0
+    } else if age < DAY {
+        2.0
+    } else if age < WEEK {
+        0.5
+    } else {
+        0.25
+    };
+
+    count * weight
+}
+
+/// Sort `projects` so the most frecent come first
+pub fn sort_by_frecency(projects: &mut [Project]) {
+    let now = utils::now();
+    projects.sort_by(|a, b| frecency_at(b, now).total_cmp(&frecency_at(a, now)));
+}
+
+/// Fuzzy match `query` against the name, tags and description of every project.
+///
+/// Name matches count double, so a project named like the query beats one that only mentions it. The match score is
+/// then boosted by the project's [`frecency`], so projects that are used often win over ones that merely match well.
+/// Projects that do not match at all are left out, the rest are sorted best match first.
+pub fn fuzzy_rank<'a>(projects: &'a [Project], query: &str) -> Vec<Match<'a>> {
+    let matcher = SkimMatcherV2::default().ignore_case();
+    let now = utils::now();
+
+    let mut matches = projects
+        .iter()
+        .filter_map(|project| {
+            let name = project
+                .name
+                .as_deref()
+                .and_then(|name| matcher.fuzzy_match(name, query))
+                .map(|score| score * 2);
+            let tags = project
+                .tags
+                .iter()
+                .flatten()
+                .filter_map(|tag| matcher.fuzzy_match(tag, query))
+                .max();
+            let desc = project.desc.as_deref().and_then(|desc| matcher.fuzzy_match(desc, query));
+
+            let score = [name, tags, desc].into_iter().flatten().max()?;
+            #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+            let score = (score as f64 * (1.0 + frecency_at(project, now).ln_1p())) as i64;
+            Some(Match { score, project })
+        })
+        .collect::<Vec<_>>();
+    matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+
+    matches
+}
+
+/// The clear winner of a ranking, if there is one.
+///
+/// That is the only match, a project whose name is exactly the query, or a match scoring at least twice the next one.
+pub fn best_match<'a>(matches: &[Match<'a>], query: &str) -> Option<&'a Project> {
+    let exact = matches
+        .iter()
+        .filter(|m| m.project.name.as_deref().is_some_and(|name| name.eq_ignore_ascii_case(query)))
+        .collect::<Vec<_>>();
+    if let [only] = exact.as_slice() {
+        return Some(only.project);
+    }
+
+    match matches {
+        [only] => Some(only.project),
+        [first, second, ..] if first.score >= second.score * 2 => Some(first.project),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(name: &str, tags: &[&str], desc: &str) -> Project {
+        Project::new(
+            Some(name.to_owned()),
+            Some(desc.to_owned()),
+            tags.iter().map(|t| (*t).to_owned()).collect(),
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_fuzzy_rank() {
+        let projects = vec![
+            project("fpm", &["cli", "rust"], "Frazzer's project manager"),
+            project("fpm-web", &["web"], "Website for fpm"),
+            project("dotfiles", &["config"], "My configuration files"),
+            project("blog", &["web"], "Personal blog"),
+        ];
+
+        let matches = fuzzy_rank(&projects, "fpm");
+        let names = matches
+            .iter()
+            .map(|m| m.project.name.as_deref().unwrap_or_default())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["fpm", "fpm-web"]);
+        assert_eq!(best_match(&matches, "fpm"), Some(&projects[0]));
+
+        let matches = fuzzy_rank(&projects, "web");
+        assert_eq!(matches.len(), 2);
+        assert_eq!(best_match(&matches, "web"), None);
+
+        let matches = fuzzy_rank(&projects, "dotf");
+        assert_eq!(best_match(&matches, "dotf"), Some(&projects[2]));
+
+        assert!(fuzzy_rank(&projects, "zzz").is_empty());
+    }
+
+    #[test]
+    fn test_frecency() {
+        let now = utils::now();
+        let mut recent = project("recent", &[], "");
+        recent.last_opened_at = Some(now - 60);
+        recent.open_count = Some(2);
+        let mut frequent = project("frequent", &[], "");
+        frequent.last_opened_at = Some(now - 2 * WEEK);
+        frequent.open_count = Some(40);
+        let mut stale = project("stale", &[], "");
+        stale.last_opened_at = Some(now - 2 * DAY);
+        stale.open_count = Some(3);
+        let never = project("never", &[], "");
+
+        assert!((frecency_at(&recent, now) - 8.0).abs() < f64::EPSILON);
+        assert!((frecency_at(&frequent, now) - 10.0).abs() < f64::EPSILON);
+        assert!((frecency_at(&stale, now) - 1.5).abs() < f64::EPSILON);
+        assert!(frecency_at(&never, now).abs() < f64::EPSILON);
+
+        let mut projects = vec![never, stale, recent, frequent];
+        sort_by_frecency(&mut projects);
+        let names = projects
+            .iter()
+            .map(|p| p.name.as_deref().unwrap_or_default())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["frequent", "recent", "stale", "never"]);
+
+        // A frequently used project beats a slightly better match that is never used
+        let mut used = project("fpm-web", &[], "");
+        used.last_opened_at = Some(now);
+        used.open_count = Some(20);
+        let projects = vec![project("fpm", &[], ""), used];
+        let matches = fuzzy_rank(&projects, "fpm");
+        assert_eq!(matches[0].project.name.as_deref(), Some("fpm-web"));
+    }
+}
@@ -0,0 +1,334 @@
+//! Reading and changing single config values, and checking a config for mistakes.
+//!
+//! Keys are the fields of [`Config`], entries of the `open`, `layouts` and `template_groups`
+//! tables are addressed with a dot, I.E. `open.rust`. Changes are made to one config file
+//! with [`ConfigFile`], which keeps the comments and formatting of the rest of the file.
+
+use crate::{
+    config::{parse_value, Config, ConfigSources, ENV_PREFIX, LOCAL_KEYS},
+    error::Error,
+    utils::Result,
+};
+use fs_err as fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use toml_edit::{Document, Item};
+
+/// Every key of the config
+pub const KEYS: [&str; 13] = [
+    "database_path",
+    "base_dir",
+    "template_dir",
+    "template_groups",
+    "git_command",
+    "editor",
+    "open",
+    "backup_dir",
+    "backup_retention",
+    "layout",
+    "layouts",
+    "backend",
+    "default_category",
+];
+/// The keys that hold a table of named values
+const TABLE_KEYS: [&str; 3] = ["template_groups", "open", "layouts"];
+
+/// A problem found by [`validate_config`]
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigProblem {
+    #[error("unknown key `{0}` in {1}")]
+    UnknownKey(String, String),
+
+    #[error("{0} could not be read: {1}")]
+    Invalid(String, String),
+
+    #[error("`{0}` is set to `{}`, which is not an existing directory", .1.display())]
+    MissingDirectory(&'static str, PathBuf),
+
+    #[error("the database `{}` is not writable: {1}", .0.display())]
+    DatabaseNotWritable(PathBuf, String),
+
+    #[error("the environment variable `{0}` is not a config key and is ignored")]
+    UnknownEnvVar(String),
+
+    #[error("`{0}` can only be set in the global config and is ignored in {1}")]
+    NotLocalKey(String, String),
+}
+
+impl ConfigProblem {
+    /// Other tools use `FPM_` variables too, I.E. the Fortran package manager, so those are only worth a warning.
+    /// Keys a repo-local config is not allowed to set are skipped, which is a warning as well.
+    pub fn is_warning(&self) -> bool {
+        matches!(self, Self::UnknownEnvVar(_) | Self::NotLocalKey(..))
+    }
+}
+
+/// Check that `key` is a config key, or an entry of one of the tables
+pub fn check_key(key: &str) -> Result<()> {
+    let valid = match key.split_once('.') {
+        Some((table, entry)) => TABLE_KEYS.contains(&table) && !entry.is_empty(),
+        None => KEYS.contains(&key),
+    };
+    if !valid {
+        return Err(Error::UnknownConfigKey(key.to_owned()));
+    }
+    Ok(())
+}
+
+/// The value of `key` in `config`, `None` if it is not set
+pub fn get_value(config: &Config, key: &str) -> Result<Option<toml::Value>> {
+    check_key(key)?;
+    let mut value = toml::Value::try_from(config)?;
+    for part in split_key(key) {
+        match value.as_table_mut().and_then(|table| table.remove(part)) {
+            Some(inner) => value = inner,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(value))
+}
+
+/// A single config file that is edited in place
+pub struct ConfigFile {
+    path: PathBuf,
+    doc: Document,
+}
+
+impl ConfigFile {
+    /// Open the config file at `path`, a missing file is treated as empty
+    pub fn open(path: &Path) -> Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(Error::IO(e)),
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            doc: content.parse()?,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Set `key` to `raw`, which is read with [`parse_value`]
+    pub fn set(&mut self, key: &str, raw: &str) -> Result<()> {
+        check_key(key)?;
+        let value = Item::Value(parse_value(key, raw).to_string().parse()?);
+
+        let mut doc = self.doc.clone();
+        match key.split_once('.') {
+            Some((table, entry)) => {
+                let Some(table) = doc.entry(table).or_insert_with(toml_edit::table).as_table_like_mut() else {
+                    return Err(Error::Generic(format!("`{table}` is not a table")));
+                };
+                table.insert(entry, value);
+            },
+            None => {
+                doc.insert(key, value);
+            },
+        }
+
+        // Make sure the new value has the right type before keeping it
+        Config::parse(&doc.to_string())?;
+        self.doc = doc;
+        Ok(())
+    }
+
+    /// Remove `key` from the file, returning whether it was set
+    pub fn unset(&mut self, key: &str) -> Result<bool> {
+        check_key(key)?;
+        let removed = match key.split_once('.') {
+            Some((table, entry)) => self
+                .doc
+                .get_mut(table)
+                .and_then(Item::as_table_like_mut)
+                .and_then(|table| table.remove(entry)),
+            None => self.doc.remove(key),
+        };
+        Ok(removed.is_some())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, self.doc.to_string())?;
+        Ok(())
+    }
+}
+
+/// Check every source of the config for unknown keys, then check that the directories and
+/// database it points to can be used
+pub fn validate_config(sources: &ConfigSources) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+
+    for (path, _) in sources.files() {
+        let source = format!("`{}`", path.display());
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => {
+                problems.push(ConfigProblem::Invalid(source, e.to_string()));
+                continue;
+            },
+        };
+        let local = Some(path) == sources.local.as_deref();
+        match toml::from_str::<toml::Table>(&content) {
+            Ok(table) => problems.extend(table.keys().filter_map(|key| {
+                if !KEYS.contains(&key.as_str()) {
+                    Some(ConfigProblem::UnknownKey(key.clone(), source.clone()))
+                } else if local && !LOCAL_KEYS.contains(&key.as_str()) {
+                    Some(ConfigProblem::NotLocalKey(key.clone(), source.clone()))
+                } else {
+                    None
+                }
+            })),
+            Err(e) => problems.push(ConfigProblem::Invalid(source, e.to_string())),
+        }
+    }
+    for (name, _) in &sources.env {
+        let key = name.strip_prefix(ENV_PREFIX).unwrap_or(name).to_lowercase();
+        let key = key.split("__").next().unwrap_or_default();
+        if !KEYS.contains(&key) {
+            problems.push(ConfigProblem::UnknownEnvVar(name.clone()));
+        }
+    }
+
+    let config = match Config::load_with(sources) {
+        Ok(config) => config,
+        Err(e) => {
+            problems.push(ConfigProblem::Invalid("the combined config".to_owned(), e.to_string()));
+            return problems;
+        },
+    };
+
+    let directories = [("base_dir", &config.base_dir), ("template_dir", &config.template_dir)];
+    for (key, dir) in directories {
+        if let Some(dir) = dir.as_ref().map(PathBuf::from).filter(|dir| !dir.is_dir()) {
+            problems.push(ConfigProblem::MissingDirectory(key, dir));
+        }
+    }
+    let path = PathBuf::from(&config.database_path);
+    if let Err(e) = check_writable(&path) {
+        problems.push(ConfigProblem::DatabaseNotWritable(path, e.to_string()));
+    }
+
+    problems
+}
+
+/// The parts of a key, I.E. `open` and `rust` for `open.rust`
+fn split_key(key: &str) -> impl Iterator<Item = &str> {
+    let (first, rest) = key.split_once('.').map_or((key, None), |(first, rest)| (first, Some(rest)));
+    std::iter::once(first).chain(rest)
+}
+
+/// Check that `path` can be written to, or created if it does not exist yet
+fn check_writable(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        return Err(std::io::Error::other("it is a directory"));
+    }
+    if path.exists() {
+        return std::fs::OpenOptions::new().append(true).open(path).map(|_| ());
+    }
+
+    // The database folder is created when it is missing, so check the closest folder that exists
+    let Some(dir) = path.ancestors().skip(1).find(|dir| dir.is_dir()) else {
+        return Err(std::io::Error::new(ErrorKind::NotFound, "none of its parent folders exist"));
+    };
+    let probe = dir.join(".fpm-write-check");
+    std::fs::File::create(&probe)?;
+    std::fs::remove_file(probe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_config_file() -> Result<()> {
+        let root = PathBuf::from("./tests/test_files/config_file");
+        let _ = std::fs::remove_dir_all(&root);
+        let path = root.join("fpm.toml");
+
+        let mut file = ConfigFile::open(&path)?;
+        file.set("base_dir", "/projects")?;
+        file.set("backup_retention", "3")?;
+        file.set("open.rust", "code {dir}")?;
+        file.set("default_category", "2024")?;
+        assert!(matches!(file.set("colour", "blue"), Err(Error::UnknownConfigKey(_))));
+        assert!(matches!(file.set("backend", "cloud"), Err(Error::TomlDes(_))));
+        // Everything would be lost when fpm exits, so the memory store can only be used from code
+        assert!(matches!(file.set("backend", "memory"), Err(Error::TomlDes(_))));
+        file.save()?;
+
+        // Comments are kept when the file is changed
+        let content = format!("# my projects\n{}", fs::read_to_string(&path)?);
+        fs::write(&path, content)?;
+        let mut file = ConfigFile::open(&path)?;
+        assert!(file.unset("backup_retention")?);
+        assert!(!file.unset("editor")?);
+        file.save()?;
+        assert!(fs::read_to_string(&path)?.starts_with("# my projects\n"));
+
+        let config = Config::parse(&fs::read_to_string(&path)?)?;
+        assert_eq!(
+            get_value(&config, "base_dir")?,
+            Some(toml::Value::String("/projects".to_owned()))
+        );
+        assert_eq!(
+            get_value(&config, "open.rust")?,
+            Some(toml::Value::String("code {dir}".to_owned()))
+        );
+        assert_eq!(
+            get_value(&config, "default_category")?,
+            Some(toml::Value::String("2024".to_owned()))
+        );
+        assert_eq!(get_value(&config, "backup_retention")?, None);
+        assert_eq!(get_value(&config, "layouts.work")?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_config() -> Result<()> {
+        let root = PathBuf::from("./tests/test_files/validate_config");
+        let _ = std::fs::remove_dir_all(&root);
+        fs::create_dir_all(&root)?;
+
+        let path = root.join("fpm.toml");
+        fs::write(
+            &path,
+            format!(
+                "database_path = \"{}\"\nbase_dir = \"{}\"\ncolour = \"blue\"\n",
+                root.join("db").join("projects.db").display(),
+                root.join("missing").display()
+            ),
+        )?;
+        let local = root.join(".fpm.toml");
+        fs::write(&local, "layout = \"{name}\"\neditor = \"vim\"\n")?;
+        let sources = ConfigSources {
+            local: Some(local),
+            explicit: Some(path),
+            env: vec![
+                ("FPM_EDITR".to_owned(), "vim".to_owned()),
+                ("FPM_FC".to_owned(), "gfortran".to_owned()),
+            ],
+            ..ConfigSources::default()
+        };
+
+        let problems = validate_config(&sources);
+        assert_eq!(problems.len(), 5, "{problems:?}");
+        assert!(matches!(&problems[0], ConfigProblem::NotLocalKey(key, _) if key == "editor"));
+        assert!(matches!(&problems[1], ConfigProblem::UnknownKey(key, _) if key == "colour"));
+        assert!(matches!(&problems[2], ConfigProblem::UnknownEnvVar(name) if name == "FPM_EDITR"));
+        assert!(matches!(&problems[3], ConfigProblem::UnknownEnvVar(name) if name == "FPM_FC"));
+        assert!(matches!(&problems[4], ConfigProblem::MissingDirectory("base_dir", _)));
+        assert_eq!(problems.iter().filter(|problem| problem.is_warning()).count(), 3);
+
+        Ok(())
+    }
+}
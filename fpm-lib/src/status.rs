@@ -0,0 +1,262 @@
+//! The git state of every project, read with libgit2 so no `git` processes are started.
+//!
+//! Repositories are read on a thread per core, which keeps a few hundred projects to a
+//! couple of seconds. The order of the results matches the order of the projects.
+
+use crate::error::Error;
+use crate::project::Project;
+use crate::utils::Result;
+use git2::{Branch, ErrorCode, Repository, StatusOptions};
+use serde::Serialize;
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct RepoStatus {
+    /// `None` when `HEAD` is detached
+    pub branch: Option<String>,
+    /// Whether there are staged, unstaged or untracked changes
+    pub dirty: bool,
+    /// The upstream of the current branch, I.E. `origin/main`
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    pub stashes: usize,
+    /// Unix timestamp of the commit `HEAD` points to, `None` before the first commit
+    pub last_commit: Option<i64>,
+}
+
+impl RepoStatus {
+    /// Whether the branch has commits that are not on its upstream, a branch without an upstream counts as unpushed
+    pub fn is_unpushed(&self) -> bool {
+        self.ahead > 0 || (self.upstream.is_none() && self.branch.is_some() && self.last_commit.is_some())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProjectStatus {
+    pub rowid: Option<i64>,
+    pub name: Option<String>,
+    pub directory: Option<PathBuf>,
+    #[serde(flatten)]
+    pub status: Option<RepoStatus>,
+    /// Why the status could not be read, I.E. the directory is not a git repository
+    pub error: Option<String>,
+}
+
+impl ProjectStatus {
+    fn new(project: &Project, result: std::result::Result<RepoStatus, String>) -> Self {
+        Self {
+            rowid: project.rowid,
+            name: project.name.clone(),
+            directory: project.directory.clone(),
+            error: result.as_ref().err().cloned(),
+            status: result.ok(),
+        }
+    }
+}
+
+/// Read the git status of every project in parallel
+pub fn project_statuses(projects: &[Project]) -> Vec<ProjectStatus> {
+    read_in_parallel(projects, project_status)
+}
+
+/// Run `read` for every project on a thread per core. A project that makes `read` panic gets an error, the panic is
+/// caught so the worker keeps the statuses it already read and goes on with the next project.
+fn read_in_parallel(projects: &[Project], read: impl Fn(&Project) -> ProjectStatus + Sync) -> Vec<ProjectStatus> {
+    let threads = thread::available_parallelism()
+        .map_or(4, NonZeroUsize::get)
+        .min(projects.len().max(1));
+    let next = AtomicUsize::new(0);
+
+    let mut results = thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = vec![];
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(project) = projects.get(i) else {
+                            break;
+                        };
+                        let status = panic::catch_unwind(AssertUnwindSafe(|| read(project))).unwrap_or_else(|_| {
+                            ProjectStatus::new(project, Err("reading the status failed unexpectedly".to_owned()))
+                        });
+                        done.push((i, status));
+                    }
+                    done
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_default())
+            .collect::<Vec<_>>()
+    });
+
+    // Workers finish in any order, so put the results back in the order of the projects
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, status)| status).collect()
+}
+
+fn project_status(project: &Project) -> ProjectStatus {
+    let result = match &project.directory {
+        Some(dir) => repo_status(dir).map_err(|e| match e {
+            Error::Git(e) if e.code() == ErrorCode::NotFound => "not a git repository".to_owned(),
+            Error::Git(e) => e.message().to_owned(),
+            e => e.to_string(),
+        }),
+        None => Err("the project does not have a directory".to_owned()),
+    };
+    ProjectStatus::new(project, result)
+}
+
+/// Read the status of the repository at `dir`
+pub fn repo_status(dir: &Path) -> Result<RepoStatus> {
+    let mut repo = Repository::open(dir)?;
+    let mut status = RepoStatus::default();
+
+    match repo.head() {
+        Ok(head) => {
+            status.last_commit = head.peel_to_commit().ok().map(|commit| commit.time().seconds());
+            if head.is_branch() {
+                status.branch = head.shorthand().map(str::to_owned);
+
+                let local = head.target();
+                if let Ok(upstream) = Branch::wrap(head).upstream() {
+                    status.upstream = upstream.name().ok().flatten().map(str::to_owned);
+                    if let (Some(local), Some(remote)) = (local, upstream.get().target()) {
+                        (status.ahead, status.behind) = repo.graph_ahead_behind(local, remote)?;
+                    }
+                }
+            }
+        },
+        // A new repository has a branch without any commits on it
+        Err(e) if e.code() == ErrorCode::UnbornBranch => {
+            status.branch = repo
+                .find_reference("HEAD")?
+                .symbolic_target()
+                .map(|target| target.trim_start_matches("refs/heads/").to_owned());
+        },
+        Err(e) => return Err(e.into()),
+    }
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .include_ignored(false)
+        .exclude_submodules(true);
+    status.dirty = !repo.statuses(Some(&mut options))?.is_empty();
+
+    repo.stash_foreach(|_, _, _| {
+        status.stashes += 1;
+        true
+    })?;
+
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use fs_err as fs;
+    use git2::{Signature, Time};
+
+    fn commit(repo: &Repository, file: &str) -> Result<()> {
+        let workdir = repo.workdir().map(Path::to_path_buf).unwrap_or_default();
+        fs::write(workdir.join(file), file)?;
+
+        let mut index = repo.index()?;
+        index.add_path(Path::new(file))?;
+        index.write()?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+
+        let signature = Signature::new("fpm", "fpm@example.com", &Time::new(1_700_000_000, 0))?;
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            file,
+            &tree,
+            parent.iter().collect::<Vec<_>>().as_slice(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_repo_status() -> Result<()> {
+        let root = PathBuf::from("./tests/test_files/status");
+        let _ = std::fs::remove_dir_all(&root);
+        fs::create_dir_all(&root)?;
+        let root = fs::canonicalize(&root)?;
+
+        let remote_dir = root.join("remote");
+        let remote = Repository::init(&remote_dir)?;
+        assert_eq!(repo_status(&remote_dir)?.last_commit, None);
+        commit(&remote, "README.md")?;
+
+        let local_dir = root.join("local");
+        let mut local = Repository::clone(&remote_dir.to_string_lossy(), &local_dir)?;
+        let status = repo_status(&local_dir)?;
+        assert!(!status.dirty && !status.is_unpushed());
+        assert_eq!(
+            status.upstream.as_deref(),
+            Some(format!("origin/{}", status.branch.unwrap_or_default()).as_str())
+        );
+        assert_eq!(status.last_commit, Some(1_700_000_000));
+
+        commit(&local, "main.rs")?;
+        commit(&remote, "LICENSE")?;
+        local.find_remote("origin")?.fetch(&[] as &[&str], None, None)?;
+        fs::write(local_dir.join("notes.txt"), "wip")?;
+        let status = repo_status(&local_dir)?;
+        assert!(status.dirty && status.is_unpushed());
+        assert_eq!((status.ahead, status.behind), (1, 1));
+
+        let signature = Signature::now("fpm", "fpm@example.com")?;
+        local.stash_save(&signature, "wip", Some(git2::StashFlags::INCLUDE_UNTRACKED))?;
+        let status = repo_status(&local_dir)?;
+        assert!(!status.dirty);
+        assert_eq!(status.stashes, 1);
+
+        let mut outside = Project::new(Some("plain".to_owned()), None, vec![], None, None);
+        outside.directory = Some(root.clone());
+        let mut cloned = Project::new(Some("local".to_owned()), None, vec![], None, None);
+        cloned.directory = Some(local_dir);
+        let statuses = project_statuses(&[outside, cloned]);
+        assert_eq!(statuses[0].name.as_deref(), Some("plain"));
+        assert_eq!(statuses[0].error.as_deref(), Some("not a git repository"));
+        assert_eq!(statuses[1].status.as_ref().map(|s| s.stashes), Some(1));
+
+        // A panic while reading one project only costs that project its status
+        let projects = (0..20)
+            .map(|i| Project::new(Some(i.to_string()), None, vec![], None, None))
+            .collect::<Vec<_>>();
+        let statuses = read_in_parallel(&projects, |project| {
+            let i = project.name.as_deref().and_then(|name| name.parse().ok()).unwrap_or_default();
+            assert_ne!(i, 7, "unreadable repository");
+            let status = RepoStatus {
+                stashes: i,
+                ..RepoStatus::default()
+            };
+            ProjectStatus::new(project, Ok(status))
+        });
+        assert_eq!(statuses.len(), 20);
+        for (i, status) in statuses.iter().enumerate() {
+            assert_eq!(status.name, Some(i.to_string()));
+            if i == 7 {
+                assert!(status.status.is_none() && status.error.is_some());
+            } else {
+                assert_eq!(status.status.as_ref().map(|status| status.stashes), Some(i), "{i}");
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use super::{MemoryStore, ProjectFilter, ProjectStore};
+use crate::{project::Project, registry::RegistryFormat, utils};
+use fs_err as fs;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A [`ProjectStore`] kept in a single JSON or TOML file, which is rewritten after every change
+#[derive(Debug)]
+pub struct FileStore {
+    path: PathBuf,
+    format: RegistryFormat,
+    memory: MemoryStore,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct StoreFile {
+    #[serde(default)]
+    projects: Vec<Project>,
+}
+
+impl FileStore {
+    /// Load the projects from `path`, the file is created on the first change if it does not exist yet
+    pub fn open(path: PathBuf) -> utils::Result<Self> {
+        let format = RegistryFormat::from_path(&path);
+        let file = if path.exists() {
+            let content = fs::read_to_string(&path)?;
+            match format {
+                RegistryFormat::Json => serde_json::from_str(&content)?,
+                RegistryFormat::Toml => toml::from_str(&content)?,
+            }
+        } else {
+            StoreFile::default()
+        };
+
+        Ok(Self {
+            path,
+            format,
+            memory: MemoryStore::from_projects(file.projects),
+        })
+    }
+
+    /// Make `change` to a copy of the projects and only keep it when it succeeded and was saved
+    fn change(&mut self, change: impl FnOnce(&mut MemoryStore) -> utils::Result<()>) -> utils::Result<()> {
+        let mut memory = self.memory.clone();
+        change(&mut memory)?;
+        let previous = std::mem::replace(&mut self.memory, memory);
+        if let Err(e) = self.save() {
+            self.memory = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Write the projects to a temporary file first, so a failed write never leaves a half written store behind
+    fn save(&self) -> utils::Result<()> {
+        let file = StoreFile {
+            projects: self.memory.projects().to_vec(),
+        };
+        let content = match self.format {
+            RegistryFormat::Json => serde_json::to_string_pretty(&file)?,
+            RegistryFormat::Toml => toml::to_string(&file)?,
+        };
+
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, content)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl ProjectStore for FileStore {
+    fn add(&mut self, project: &Project) -> utils::Result<i64> {
+        let rowid = self.memory.add(project)?;
+        self.save()?;
+        Ok(rowid)
+    }
+
+    fn get(&self, rowid: i64) -> utils::Result<Option<Project>> {
+        self.memory.get(rowid)
+    }
+
+    fn update(&mut self, project: &Project) -> utils::Result<()> {
+        self.memory.update(project)?;
+        self.save()
+    }
+
+    fn remove(&mut self, project: &Project) -> utils::Result<()> {
+        self.memory.remove(project)?;
+        self.save()
+    }
+
+    fn query(&self, filter: &ProjectFilter) -> utils::Result<Vec<Project>> {
+        self.memory.query(filter)
+    }
+
+    /// Either all of the projects are added or none are
+    fn add_projects(&mut self, projects: &[Project]) -> utils::Result<()> {
+        self.change(|memory| memory.add_projects(projects))
+    }
+
+    fn apply_changes(&mut self, add: &[Project], update: &[Project]) -> utils::Result<()> {
+        self.change(|memory| memory.apply_changes(add, update))
+    }
+}
@@ -0,0 +1,71 @@
+use super::{ProjectFilter, ProjectStore};
+use crate::{error::Error, project::Project, utils};
+
+/// A [`ProjectStore`] that only keeps the projects in memory, mostly useful for tests
+#[derive(Debug, Default, Clone)]
+pub struct MemoryStore {
+    projects: Vec<Project>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_projects(projects: Vec<Project>) -> Self {
+        Self { projects }
+    }
+
+    pub fn projects(&self) -> &[Project] {
+        &self.projects
+    }
+
+    fn position(&self, project: &Project, action: &'static str) -> utils::Result<usize> {
+        let Some(rowid) = project.rowid else {
+            return Err(Error::Static(action));
+        };
+        self.projects
+            .iter()
+            .position(|p| p.rowid == Some(rowid))
+            .ok_or_else(|| Error::ProjectNotFound(rowid.to_string()))
+    }
+}
+
+impl ProjectStore for MemoryStore {
+    fn add(&mut self, project: &Project) -> utils::Result<i64> {
+        if project.rowid.is_some() {
+            return Err(Error::Static("cannot add a project that is already in the database"));
+        }
+
+        let rowid = self.projects.iter().filter_map(|p| p.rowid).max().unwrap_or_default() + 1;
+        self.projects.push(Project {
+            rowid: Some(rowid),
+            tags: Some(project.tags.clone().unwrap_or_default()),
+            ..project.clone()
+        });
+        Ok(rowid)
+    }
+
+    fn get(&self, rowid: i64) -> utils::Result<Option<Project>> {
+        Ok(self.projects.iter().find(|p| p.rowid == Some(rowid)).cloned())
+    }
+
+    fn update(&mut self, project: &Project) -> utils::Result<()> {
+        let i = self.position(project, "cannot update a project that has not been added to the database")?;
+        self.projects[i] = Project {
+            tags: Some(project.tags.clone().unwrap_or_default()),
+            ..project.clone()
+        };
+        Ok(())
+    }
+
+    fn remove(&mut self, project: &Project) -> utils::Result<()> {
+        let i = self.position(project, "cannot remove a project that has not been added to the database")?;
+        self.projects.remove(i);
+        Ok(())
+    }
+
+    fn query(&self, filter: &ProjectFilter) -> utils::Result<Vec<Project>> {
+        Ok(self.projects.iter().filter(|p| filter.matches(p)).cloned().collect())
+    }
+}
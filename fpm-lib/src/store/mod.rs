@@ -0,0 +1,524 @@
+//! Storage backends for the projects.
+//!
+//! Every backend implements [`ProjectStore`], the backend used by the CLI is picked with
+//! the `backend` key of the config:
+//!
+//! - `sqlite`, the default, a [`ProjectDb`] at `database_path`
+//! - `file`, a [`FileStore`] at `database_path`, stored as TOML if the path ends in `.toml` and as JSON otherwise.
+//!   Meant to be committed to a dotfiles repository
+//!
+//! [`MemoryStore`] can not be picked in the config, as everything written to it would be lost when fpm exits. It is
+//! meant for tests and for embedding fpm-lib.
+
+mod file;
+mod memory;
+
+pub use file::FileStore;
+pub use memory::MemoryStore;
+
+use crate::{config::Config, database::ProjectDb, error::Error, project::Project, utils};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Sqlite,
+    File,
+}
+
+/// Criteria for [`ProjectStore::query`], every criteria that is set has to match
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProjectFilter {
+    /// Projects must have all of these tags
+    pub tags: Vec<String>,
+    pub language: Option<String>,
+    pub category: Option<String>,
+    /// Glob pattern the name must match, I.E. `fpm-*`
+    pub name: Option<String>,
+    /// Text that has to appear in the name or description
+    pub search: Option<String>,
+}
+
+impl ProjectFilter {
+    /// Check `project` against the filter the same way the SQLite backend does
+    pub fn matches(&self, project: &Project) -> bool {
+        let eq_ignore_case = |wanted: &Option<String>, value: &Option<String>| match (wanted, value) {
+            (None, _) => true,
+            (Some(wanted), Some(value)) => wanted.to_lowercase() == value.to_lowercase(),
+            (Some(_), None) => false,
+        };
+        let contains =
+            |value: &Option<String>, search: &str| value.as_ref().is_some_and(|value| value.to_lowercase().contains(search));
+
+        let tags = project.tags.as_deref().unwrap_or_default();
+
+        eq_ignore_case(&self.language, &project.language)
+            && eq_ignore_case(&self.category, &project.category)
+            && self
+                .name
+                .as_ref()
+                .is_none_or(|pattern| project.name.as_ref().is_some_and(|name| glob_match(pattern, name)))
+            && self
+                .search
+                .as_ref()
+                .map(|s| s.to_lowercase())
+                .is_none_or(|search| contains(&project.name, &search) || contains(&project.desc, &search))
+            && self.tags.iter().all(|tag| tags.contains(tag))
+    }
+}
+
+/// Somewhere projects can be kept.
+///
+/// Only the basic operations have to be implemented, the rest are built on top of them and can be
+/// overridden when the backend can do better.
+pub trait ProjectStore {
+    /// Add `project`, returning its new `rowid`
+    fn add(&mut self, project: &Project) -> utils::Result<i64>;
+
+    fn get(&self, rowid: i64) -> utils::Result<Option<Project>>;
+
+    /// Replace the project with the same `rowid`
+    fn update(&mut self, project: &Project) -> utils::Result<()>;
+
+    fn remove(&mut self, project: &Project) -> utils::Result<()>;
+
+    /// The projects matching `filter`
+    fn query(&self, filter: &ProjectFilter) -> utils::Result<Vec<Project>>;
+
+    fn add_projects(&mut self, projects: &[Project]) -> utils::Result<()> {
+        for project in projects {
+            self.add(project)?;
+        }
+        Ok(())
+    }
+
+    fn load_projects(&self) -> utils::Result<Vec<Project>> {
+        self.query(&ProjectFilter::default())
+    }
+
+    /// Add `add` and replace the projects in `update` as a single change, either all of it is stored or none of it.
+    ///
+    /// The changes that were already made are undone when one of them fails.
+    fn apply_changes(&mut self, add: &[Project], update: &[Project]) -> utils::Result<()> {
+        let originals = update
+            .iter()
+            .filter_map(|project| project.rowid)
+            .map(|rowid| self.get(rowid))
+            .collect::<utils::Result<Vec<_>>>()?;
+
+        let mut added = Vec::new();
+        let result = add
+            .iter()
+            .try_for_each(|project| self.add(project).map(|rowid| added.push(rowid)))
+            .and_then(|()| update.iter().try_for_each(|project| self.update(project)));
+
+        if let Err(e) = result {
+            for rowid in added {
+                self.remove(&Project {
+                    rowid: Some(rowid),
+                    ..Project::default()
+                })?;
+            }
+            for original in originals.into_iter().flatten() {
+                self.update(&original)?;
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Find a single project by its name or, if no project has that name and `key` is a number, by its `rowid`.
+    ///
+    /// Names are checked first so a project named I.E. `2024` can still be found by its name.
+    fn find_project(&self, key: &str) -> utils::Result<Project> {
+        if let Some(project) = self.load_projects()?.into_iter().find(|p| p.name.as_deref() == Some(key)) {
+            return Ok(project);
+        }
+        let project = match key.parse::<i64>() {
+            Ok(rowid) => self.get(rowid)?,
+            Err(_) => None,
+        };
+
+        project.ok_or_else(|| Error::ProjectNotFound(key.to_owned()))
+    }
+
+    /// Move the project's directory to the folder generated from its current metadata and store the new location.
+    ///
+    /// If the store can not be updated the directory is moved back, so the store and the filesystem stay in sync.
+    /// Returns the new directory of the project.
+    fn move_project(&mut self, config: &Config, project: &mut Project) -> utils::Result<PathBuf> {
+        let target = config.gen_project_folder(project)?;
+
+        // `project` may already hold edited values, the folder on disk is where the store last put it
+        let stored = project.rowid.map(|rowid| self.get(rowid)).transpose()?.flatten();
+        let Some(current) = stored.map_or_else(|| project.directory.clone(), |stored| stored.directory) else {
+            return Err(Error::Static("project does not have a directory to move"));
+        };
+        if current == target {
+            self.update(project)?;
+            return Ok(target);
+        }
+
+        utils::move_dir(&current, &target)?;
+        project.directory = Some(target.clone());
+
+        if let Err(e) = self.update(project) {
+            project.directory = Some(current.clone());
+            utils::move_dir(&target, &current)?;
+            return Err(e);
+        }
+
+        Ok(target)
+    }
+
+    /// Record that the project was just opened, this feeds into the frecency ranking
+    fn record_open(&mut self, project: &mut Project) -> utils::Result<()> {
+        project.last_opened_at = Some(utils::now());
+        project.open_count = Some(project.open_count.unwrap_or_default() + 1);
+
+        self.update(project)
+    }
+
+    /// Every tag in use along with the number of projects that have it
+    fn tag_counts(&self) -> utils::Result<Vec<(String, i64)>> {
+        let mut counts = BTreeMap::new();
+        for project in self.load_projects()? {
+            for tag in project.tags.unwrap_or_default() {
+                *counts.entry(tag).or_insert(0) += 1;
+            }
+        }
+        Ok(counts.into_iter().collect())
+    }
+
+    /// Rename `old` to `new` on every project, merging the two if a project already has both.
+    /// Returns the number of projects that had the tag.
+    fn rename_tag(&mut self, old: &str, new: &str) -> utils::Result<usize> {
+        if old == new {
+            return Ok(0);
+        }
+        let mut count = 0;
+        for mut project in self.load_projects()? {
+            let tags = project.tags.get_or_insert_with(Vec::new);
+            if !tags.iter().any(|tag| tag == old) {
+                continue;
+            }
+            if tags.iter().any(|tag| tag == new) {
+                tags.retain(|tag| tag != old);
+            } else {
+                for tag in tags.iter_mut().filter(|tag| *tag == old) {
+                    *tag = new.to_owned();
+                }
+            }
+            self.update(&project)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn add_tag(&mut self, project: &Project, tag: &str) -> utils::Result<()> {
+        let Some(mut project) = project.rowid.map(|rowid| self.get(rowid)).transpose()?.flatten() else {
+            return Err(Error::Static("cannot tag a project that has not been added to the database"));
+        };
+        let tags = project.tags.get_or_insert_with(Vec::new);
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_owned());
+            self.update(&project)?;
+        }
+        Ok(())
+    }
+
+    /// Remove `tag` from `project`, returns `false` if the project did not have the tag
+    fn remove_tag(&mut self, project: &Project, tag: &str) -> utils::Result<bool> {
+        let Some(mut project) = project.rowid.map(|rowid| self.get(rowid)).transpose()?.flatten() else {
+            return Ok(false);
+        };
+        let tags = project.tags.get_or_insert_with(Vec::new);
+        let before = tags.len();
+        tags.retain(|t| t != tag);
+        if tags.len() == before {
+            return Ok(false);
+        }
+        self.update(&project)?;
+        Ok(true)
+    }
+
+    /// Remove tags that are blank, returns the number of tags removed
+    fn prune_tags(&mut self) -> utils::Result<usize> {
+        let mut count = 0;
+        for mut project in self.load_projects()? {
+            let tags = project.tags.get_or_insert_with(Vec::new);
+            let before = tags.len();
+            tags.retain(|tag| !tag.trim().is_empty());
+            if tags.len() != before {
+                count += before - tags.len();
+                self.update(&project)?;
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// Open the store selected by the `backend` key of `config`
+pub fn open_store(config: &Config) -> utils::Result<Box<dyn ProjectStore>> {
+    match config.backend {
+        Backend::Sqlite => Ok(Box::new(ProjectDb::open(config)?)),
+        Backend::File => Ok(Box::new(FileStore::open(PathBuf::from(&config.database_path))?)),
+    }
+}
+
+/// SQLite style `GLOB` matching, supporting `*`, `?` and `[...]` character classes
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    glob_match_chars(&pattern, &text)
+}
+
+fn glob_match_chars(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') => (0..=text.len()).any(|i| glob_match_chars(&pattern[1..], &text[i..])),
+        Some('?') => !text.is_empty() && glob_match_chars(&pattern[1..], &text[1..]),
+        Some('[') => {
+            let Some(end) = pattern.iter().skip(2).position(|&c| c == ']').map(|i| i + 2) else {
+                return text.first() == Some(&'[') && glob_match_chars(&pattern[1..], &text[1..]);
+            };
+            let Some(&c) = text.first() else {
+                return false;
+            };
+            let (negated, class) = match pattern[1] {
+                '^' => (true, &pattern[2..end]),
+                _ => (false, &pattern[1..end]),
+            };
+            let mut matched = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    matched |= class[i] <= c && c <= class[i + 2];
+                    i += 3;
+                } else {
+                    matched |= class[i] == c;
+                    i += 1;
+                }
+            }
+            matched != negated && glob_match_chars(&pattern[end + 1..], &text[1..])
+        },
+        Some(&p) => text.first() == Some(&p) && glob_match_chars(&pattern[1..], &text[1..]),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::path::Path;
+
+    pub(crate) fn project(name: &str, language: &str, tags: &[&str]) -> Project {
+        Project {
+            name: Some(name.to_owned()),
+            desc: Some(format!("the {name} project")),
+            language: Some(language.to_owned()),
+            tags: Some(tags.iter().map(|&t| t.to_owned()).collect()),
+            ..Project::default()
+        }
+    }
+
+    /// One of every store, the file store is written to `path`
+    fn all_stores(path: &Path) -> Result<Vec<Box<dyn ProjectStore>>> {
+        let _ = std::fs::remove_file(path);
+        Ok(vec![
+            Box::new(ProjectDb::open_in_memory()?),
+            Box::new(MemoryStore::new()),
+            Box::new(FileStore::open(path.to_path_buf())?),
+        ])
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("fpm-*", "fpm-cli"));
+        assert!(!glob_match("fpm-*", "FPM-cli"));
+        assert!(glob_match("f?m", "fpm"));
+        assert!(glob_match("[a-f]pm", "fpm"));
+        assert!(!glob_match("[^a-f]pm", "fpm"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("fpm", "fpm-cli"));
+    }
+
+    /// Every backend has to behave the same
+    #[test]
+    fn test_stores() -> Result<()> {
+        let path = PathBuf::from("./tests/test_files/store.toml");
+        for mut store in all_stores(&path)? {
+            store.add_projects(&[
+                project("fpm-cli", "rust", &["cli", "tool"]),
+                project("fpm-lib", "Rust", &["lib"]),
+                project("site", "go", &["web", "tool"]),
+            ])?;
+
+            let names = |projects: Vec<Project>| projects.into_iter().filter_map(|p| p.name).collect::<Vec<_>>();
+            let filter = |f: ProjectFilter| store.query(&f).map(names);
+
+            assert_eq!(
+                filter(ProjectFilter {
+                    language: Some("RUST".to_owned()),
+                    ..ProjectFilter::default()
+                })?,
+                vec!["fpm-cli", "fpm-lib"]
+            );
+            assert_eq!(
+                filter(ProjectFilter {
+                    tags: vec!["tool".to_owned()],
+                    name: Some("fpm-*".to_owned()),
+                    ..ProjectFilter::default()
+                })?,
+                vec!["fpm-cli"]
+            );
+            assert_eq!(
+                filter(ProjectFilter {
+                    search: Some("SITE".to_owned()),
+                    ..ProjectFilter::default()
+                })?,
+                vec!["site"]
+            );
+
+            let mut site = store.find_project("site")?;
+            assert_eq!(store.get(site.rowid.unwrap_or_default())?, Some(site.clone()));
+            store.record_open(&mut site)?;
+            assert_eq!(store.find_project("site")?.open_count, Some(1));
+
+            assert_eq!(store.rename_tag("tool", "cli")?, 2);
+            store.add_tag(&site, " ")?;
+            assert!(store.remove_tag(&site, "web")?);
+            assert_eq!(store.prune_tags()?, 1);
+            assert_eq!(store.tag_counts()?, vec![("cli".to_owned(), 2), ("lib".to_owned(), 1)]);
+
+            store.remove(&site)?;
+            assert!(matches!(store.find_project("site"), Err(Error::ProjectNotFound(_))));
+            assert_eq!(store.load_projects()?.len(), 2);
+        }
+
+        // The file store was written to disk
+        assert_eq!(FileStore::open(path)?.load_projects()?.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_project() -> Result<()> {
+        let root = PathBuf::from("./tests/test_files/move_project");
+        let _ = std::fs::remove_dir_all(&root);
+        let old_dir = root.join("old");
+        std::fs::create_dir_all(&old_dir)?;
+        std::fs::write(old_dir.join("README.md"), "fpm")?;
+
+        let config = Config {
+            base_dir: Some(root.join("projects").display().to_string()),
+            layout: Some("{name}".to_owned()),
+            ..Config::new()
+        };
+        let mut store = ProjectDb::open_in_memory()?;
+        let mut app = project("app", "rust", &[]);
+        app.directory = Some(old_dir.clone());
+        store.add(&app)?;
+
+        // An edited directory is not where the folder is on disk, the stored one is
+        let mut app = store.find_project("app")?;
+        app.directory = Some(root.join("elsewhere"));
+        let target = store.move_project(&config, &mut app)?;
+
+        assert!(target.ends_with("projects/app"));
+        assert!(target.join("README.md").is_file());
+        assert!(!old_dir.exists());
+        assert_eq!(store.find_project("app")?.directory, Some(target));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_changes() -> Result<()> {
+        let path = PathBuf::from("./tests/test_files/store_changes.toml");
+        for mut store in all_stores(&path)? {
+            store.add(&project("one", "rust", &[]))?;
+            let mut one = store.find_project("one")?;
+            one.language = Some("go".to_owned());
+            let missing = Project {
+                rowid: Some(99),
+                ..project("missing", "c", &[])
+            };
+
+            // The update of a project that is not stored fails, so nothing else is kept either
+            let result = store.apply_changes(&[project("two", "rust", &[])], &[one.clone(), missing]);
+            assert!(matches!(result, Err(Error::ProjectNotFound(rowid)) if rowid == "99"));
+            assert_eq!(store.load_projects()?.len(), 1);
+            assert_eq!(store.find_project("one")?.language.as_deref(), Some("rust"));
+
+            store.apply_changes(&[project("two", "rust", &[])], &[one])?;
+            assert_eq!(store.load_projects()?.len(), 2);
+            assert_eq!(store.find_project("one")?.language.as_deref(), Some("go"));
+        }
+
+        assert_eq!(FileStore::open(path)?.load_projects()?.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search() -> Result<()> {
+        for mut store in all_stores(Path::new("./tests/test_files/store_search.toml"))? {
+            store.add_projects(&[
+                project("100%_done", "rust", &[]),
+                project("1000-done", "rust", &[]),
+                project(r"C:\dev", "rust", &[]),
+            ])?;
+
+            let search = |text: &str| {
+                let filter = ProjectFilter {
+                    search: Some(text.to_owned()),
+                    ..ProjectFilter::default()
+                };
+                store
+                    .query(&filter)
+                    .map(|projects| projects.into_iter().filter_map(|p| p.name).collect::<Vec<_>>())
+            };
+
+            // Wildcards of SQL `LIKE` are matched literally
+            assert_eq!(search("0%")?, vec!["100%_done"]);
+            assert_eq!(search("_")?, vec!["100%_done"]);
+            assert_eq!(search(r"\")?, vec![r"C:\dev"]);
+            assert_eq!(search("DONE")?, vec!["100%_done", "1000-done"]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tags() -> Result<()> {
+        for mut store in all_stores(Path::new("./tests/test_files/store_tags.toml"))? {
+            store.add_projects(&[project("one", "rust", &["cli", "lib"]), project("two", "go", &["cli"])])?;
+            let counts = vec![("cli".to_owned(), 2), ("lib".to_owned(), 1)];
+
+            // Renaming a tag to itself used to remove it from every project
+            assert_eq!(store.rename_tag("cli", "cli")?, 0);
+            assert_eq!(store.tag_counts()?, counts);
+            assert_eq!(store.rename_tag("web", "cli")?, 0);
+            assert_eq!(store.tag_counts()?, counts);
+
+            let one = store.find_project("one")?;
+            store.add_tag(&one, "cli")?;
+            assert_eq!(store.tag_counts()?, counts);
+            assert!(!store.remove_tag(&one, "web")?);
+            assert!(matches!(
+                store.add_tag(&project("new", "c", &[]), "cli"),
+                Err(Error::Static(_))
+            ));
+
+            assert_eq!(store.rename_tag("lib", "cli")?, 1);
+            assert_eq!(store.find_project("one")?.tags, Some(vec!["cli".to_owned()]));
+            assert_eq!(store.prune_tags()?, 0);
+        }
+
+        Ok(())
+    }
+}
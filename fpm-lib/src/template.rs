@@ -0,0 +1,275 @@
+//! Templates are folders of files that get copied into newly created projects.
+//!
+//! Several templates can be layered on top of each other, either by naming them one after another or by defining a
+//! group in the config:
+//!
+//! ```toml
+//! [template_groups]
+//! rust = ["base", "rust"]
+//! work-rust = ["rust", "work"]
+//! ```
+//!
+//! Groups can include other groups and are expanded in place, a group may also include the template it shares its
+//! name with. Templates are layered in the resulting order, so when
+//! two templates contain the same file, the last one wins. Files that are already in the project folder are never
+//! overwritten.
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::project::Project;
+use crate::utils::Result;
+use crate::validate::validate_segment;
+use fs_err as fs;
+use std::path::{Path, PathBuf};
+
+/// A folder of files that get copied into newly created projects.
+///
+/// Any `{{name}}`, `{{desc}}`, `{{language}}`, `{{category}}` or `{{tags}}` in a file name or in the contents of a
+/// text file is replaced with the matching value of the project. A file name that renders to anything but a single
+/// valid path segment is an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Template {
+    /// Find the template called `name` in the template folder of the config
+    pub fn load(config: &Config, name: &str) -> Result<Self> {
+        // A name like `../x` would read a folder outside of the template folder
+        validate_segment("template", name)?;
+        let path = config.template_folder().join(name);
+
+        if !path.is_dir() {
+            return Err(Error::TemplateNotFound(name.to_owned()));
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            path,
+        })
+    }
+
+    /// Copy the template into `dir`, substituting the project's values. Files that already exist in `dir` are skipped.
+    pub fn apply(&self, project: &Project, dir: &Path) -> Result<()> {
+        copy_rendered(&self.path, dir, project)
+    }
+}
+
+/// Expand `names` into the ordered list of templates to apply.
+///
+/// Each name is looked up as a template group first and as a template second.
+pub fn resolve_templates(config: &Config, names: &[String]) -> Result<Vec<Template>> {
+    let mut templates = vec![];
+    let mut stack = vec![];
+    for name in names {
+        expand(config, name, &mut stack, &mut templates)?;
+    }
+    Ok(templates)
+}
+
+fn expand(config: &Config, name: &str, stack: &mut Vec<String>, templates: &mut Vec<Template>) -> Result<()> {
+    let Some(group) = config.template_groups.get(name) else {
+        templates.push(Template::load(config, name)?);
+        return Ok(());
+    };
+
+    if stack.iter().any(|n| n == name) {
+        return Err(Error::TemplateGroupCycle(name.to_owned()));
+    }
+
+    stack.push(name.to_owned());
+    for member in group {
+        // A group may include the template it shares its name with
+        if member == name {
+            templates.push(Template::load(config, member)?);
+        } else {
+            expand(config, member, stack, templates)?;
+        }
+    }
+    stack.pop();
+
+    Ok(())
+}
+
+/// List every template in the template folder of the config
+pub fn list_templates(config: &Config) -> Result<Vec<Template>> {
+    let folder = config.template_folder();
+    if !folder.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut templates = vec![];
+    for entry in fs::read_dir(folder)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            templates.push(Template {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                path: entry.path(),
+            });
+        }
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(templates)
+}
+
+/// Replace the template variables in `text` with the values of `project`
+pub fn render(text: &str, project: &Project) -> String {
+    let tags = project.tags.clone().unwrap_or_default().join(", ");
+    let variables = [
+        ("name", project.name.as_deref().unwrap_or_default()),
+        ("desc", project.desc.as_deref().unwrap_or_default()),
+        ("language", project.language.as_deref().unwrap_or_default()),
+        ("category", project.category.as_deref().unwrap_or_default()),
+        ("tags", tags.as_str()),
+    ];
+
+    let mut text = text.to_owned();
+    for (key, value) in variables {
+        text = text.replace(&format!("{{{{{key}}}}}"), value);
+    }
+    text
+}
+
+fn copy_rendered(from: &Path, to: &Path, project: &Project) -> Result<()> {
+    fs::create_dir_all(to)?;
+
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let file_name = render(&entry.file_name().to_string_lossy(), project);
+        // A value like `../x` in the project would otherwise place the file outside of `to`
+        validate_segment("template file name", &file_name)?;
+        let target = to.join(file_name);
+
+        if entry.file_type()?.is_dir() {
+            copy_rendered(&entry.path(), &target, project)?;
+        } else if !target.exists() {
+            let bytes = fs::read(entry.path())?;
+            // Only text files get their contents rendered, anything else is copied as is
+            match String::from_utf8(bytes) {
+                Ok(text) => fs::write(target, render(&text, project))?,
+                Err(e) => fs::write(target, e.into_bytes())?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_apply_template() -> Result<()> {
+        let root = PathBuf::from("./tests/test_files/template");
+        let _ = std::fs::remove_dir_all(&root);
+
+        let template_dir = root.join("templates").join("rust-cli");
+        fs::create_dir_all(template_dir.join("src"))?;
+        fs::write(template_dir.join("Cargo.toml"), "[package]\nname = \"{{name}}\"\n")?;
+        fs::write(template_dir.join("src").join("{{name}}.rs"), "// {{desc}} [{{tags}}]\n")?;
+        fs::write(template_dir.join("logo.bin"), [0xff, 0xfe, 0x00])?;
+
+        let config = Config {
+            template_dir: Some(root.join("templates").to_string_lossy().into_owned()),
+            ..Config::new()
+        };
+        let project = Project::new(
+            Some("demo".to_owned()),
+            Some("A demo".to_owned()),
+            vec!["cli".to_owned(), "tool".to_owned()],
+            Some("rust".to_owned()),
+            None,
+        );
+
+        let template = Template::load(&config, "rust-cli")?;
+        assert_eq!(list_templates(&config)?, vec![template.clone()]);
+        assert!(matches!(Template::load(&config, "missing"), Err(Error::TemplateNotFound(_))));
+        for name in ["../rust-cli", "..", "/tmp"] {
+            assert!(matches!(Template::load(&config, name), Err(Error::InvalidName(..))), "{name}");
+        }
+
+        // Files that are already in the folder are kept
+        let existing = root.join("existing");
+        fs::create_dir_all(&existing)?;
+        fs::write(existing.join("Cargo.toml"), "[workspace]\n")?;
+        template.apply(&project, &existing)?;
+        assert_eq!(fs::read_to_string(existing.join("Cargo.toml"))?, "[workspace]\n");
+        assert!(existing.join("src").join("demo.rs").exists());
+
+        // A rendered file name must stay a single file name
+        let notes_dir = root.join("templates").join("notes");
+        fs::create_dir_all(&notes_dir)?;
+        fs::write(notes_dir.join("{{desc}}.md"), "")?;
+        let mut escaping = project.clone();
+        escaping.desc = Some("../../escape".to_owned());
+        assert!(matches!(
+            Template::load(&config, "notes")?.apply(&escaping, &root.join("escaping")),
+            Err(Error::InvalidName(..))
+        ));
+        assert!(!root.join("escape.md").exists());
+
+        let out = root.join("demo");
+        template.apply(&project, &out)?;
+
+        assert_eq!(fs::read_to_string(out.join("Cargo.toml"))?, "[package]\nname = \"demo\"\n");
+        assert_eq!(
+            fs::read_to_string(out.join("src").join("demo.rs"))?,
+            "// A demo [cli, tool]\n"
+        );
+        assert_eq!(fs::read(out.join("logo.bin"))?, vec![0xff, 0xfe, 0x00]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_template_groups() -> Result<()> {
+        let root = PathBuf::from("./tests/test_files/template_groups");
+        let _ = std::fs::remove_dir_all(&root);
+
+        let templates = root.join("templates");
+        for (template, file, content) in [
+            ("base", "LICENSE", "MIT"),
+            ("base", "README.md", "# {{name}}"),
+            ("rust", "README.md", "# {{name}} (rust)"),
+            ("work", ".editorconfig", "root = true"),
+        ] {
+            fs::create_dir_all(templates.join(template))?;
+            fs::write(templates.join(template).join(file), content)?;
+        }
+
+        let mut config = Config {
+            template_dir: Some(templates.to_string_lossy().into_owned()),
+            ..Config::new()
+        };
+        config
+            .template_groups
+            .insert("rust".to_owned(), vec!["base".to_owned(), "rust".to_owned()]);
+        config
+            .template_groups
+            .insert("work-rust".to_owned(), vec!["rust".to_owned(), "work".to_owned()]);
+
+        let resolved = resolve_templates(&config, &["work-rust".to_owned()])?;
+        let names = resolved.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["base", "rust", "work"]);
+
+        let mut project = Project::new(Some("layered".to_owned()), None, vec![], None, None);
+        let out = root.join("layered");
+        project.build(Some(out.clone()), &resolved, &config)?;
+
+        assert_eq!(fs::read_to_string(out.join("LICENSE"))?, "MIT");
+        assert_eq!(fs::read_to_string(out.join("README.md"))?, "# layered (rust)");
+        assert_eq!(fs::read_to_string(out.join(".editorconfig"))?, "root = true");
+
+        config.template_groups.insert("base".to_owned(), vec!["work-rust".to_owned()]);
+        assert!(matches!(
+            resolve_templates(&config, &["rust".to_owned()]),
+            Err(Error::TemplateGroupCycle(_))
+        ));
+
+        Ok(())
+    }
+}
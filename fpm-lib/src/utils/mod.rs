@@ -1,6 +1,9 @@
 use crate::error::Error;
 use dirs::config_dir;
-use std::path::PathBuf;
+use fs_err as fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -9,3 +12,128 @@ pub fn config_folder() -> PathBuf {
     config_folder.push("fpm");
     config_folder
 }
+
+/// The current time as a unix timestamp in seconds
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX))
+        .unwrap_or_default()
+}
+
+/// Recursively copy the contents of `from` into `to`, including hidden files such as `.git`
+pub fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let target = to.join(entry.file_name());
+
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else if file_type.is_symlink() {
+            copy_symlink(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> Result<()> {
+    let link = fs::read_link(from)?;
+    std::os::unix::fs::symlink(link, to)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(from: &Path, to: &Path) -> Result<()> {
+    fs::copy(from, to)?;
+    Ok(())
+}
+
+/// Move a directory from `from` to `to`.
+///
+/// A plain rename is tried first, only if the paths are on different filesystems the directory is copied and the
+/// original removed afterwards. `to` can not be inside `from`.
+pub fn move_dir(from: &Path, to: &Path) -> Result<()> {
+    if to.exists() {
+        return Err(Error::DirectoryExists(to.to_path_buf()));
+    }
+    if resolve(to)?.starts_with(fs::canonicalize(from)?) {
+        return Err(Error::InvalidName(
+            "directory".to_owned(),
+            to.display().to_string(),
+            format!("it is inside `{}`, which is being moved", from.display()),
+        ));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    match fs::rename(from, to) {
+        Ok(()) => return Ok(()),
+        Err(e) if e.kind() != ErrorKind::CrossesDevices => return Err(e.into()),
+        Err(_) => {},
+    }
+
+    if let Err(e) = copy_dir(from, to) {
+        let _ = fs::remove_dir_all(to);
+        return Err(e);
+    }
+    fs::remove_dir_all(from)?;
+
+    Ok(())
+}
+
+/// The absolute form of `path`, resolving symlinks in the part of it that exists
+fn resolve(path: &Path) -> Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    for existing in path.ancestors() {
+        if let Ok(resolved) = fs::canonicalize(existing) {
+            let rest = path.strip_prefix(existing).unwrap_or(Path::new(""));
+            return Ok(resolved.join(rest));
+        }
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_move_dir() -> Result<()> {
+        let root = PathBuf::from("./tests/test_files/move_dir");
+        let _ = std::fs::remove_dir_all(&root);
+
+        let from = root.join("from");
+        fs::create_dir_all(from.join(".git"))?;
+        fs::write(from.join(".git").join("HEAD"), "ref: refs/heads/main")?;
+        fs::write(from.join("main.rs"), "fn main() {}")?;
+
+        let copy = root.join("copy");
+        copy_dir(&from, &copy)?;
+        assert_eq!(fs::read_to_string(copy.join(".git").join("HEAD"))?, "ref: refs/heads/main");
+        assert_eq!(fs::read_to_string(copy.join("main.rs"))?, "fn main() {}");
+
+        let to = root.join("nested").join("to");
+        move_dir(&from, &to)?;
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(to.join(".git").join("HEAD"))?, "ref: refs/heads/main");
+
+        assert!(matches!(move_dir(&copy, &to), Err(Error::DirectoryExists(_))));
+
+        // Moving a folder into itself is refused before anything is touched
+        let inside = copy.join("nested").join("copy");
+        assert!(matches!(move_dir(&copy, &inside), Err(Error::InvalidName(..))));
+        assert!(!copy.join("nested").exists());
+        assert!(matches!(move_dir(&root.join("missing"), &root.join("to")), Err(Error::IO(_))));
+
+        Ok(())
+    }
+}
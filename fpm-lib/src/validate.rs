@@ -0,0 +1,152 @@
+//! Checks that keep project names and generated directories safe to use as paths.
+//!
+//! Names, categories and languages end up as folder names, so they must be a single path
+//! segment that is valid on every platform: not empty, no `/` or `\`, not `.` or `..`, none of
+//! the characters Windows forbids (`<>:"|?*` and control characters), no trailing dot or
+//! space, and not a reserved Windows device name such as `CON` or `COM1`.
+
+use crate::{error::Error, project::Project, utils::Result};
+use std::path::{Component, Path, PathBuf};
+
+const FORBIDDEN_CHARS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1", "LPT2",
+    "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Check that `value` can be used as a single folder name, `field` is used in the error
+pub fn validate_segment(field: &str, value: &str) -> Result<()> {
+    let invalid = |reason: &str| Err(Error::InvalidName(field.to_owned(), value.to_owned(), reason.to_owned()));
+
+    if value.trim().is_empty() {
+        return invalid("it is empty");
+    }
+    if value == "." || value == ".." {
+        return invalid("it refers to a relative directory");
+    }
+    if let Some(c) = value.chars().find(|c| FORBIDDEN_CHARS.contains(c) || c.is_control()) {
+        return invalid(&format!("it contains the character {c:?}"));
+    }
+    if value.ends_with('.') || value.ends_with(' ') {
+        return invalid("it ends with a dot or a space");
+    }
+    let stem = value.split('.').next().unwrap_or_default().trim_end();
+    if RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem)) {
+        return invalid("it is a reserved name on Windows");
+    }
+
+    Ok(())
+}
+
+/// Check the fields of `project` that can end up in its generated directory
+pub fn validate_project(project: &Project) -> Result<()> {
+    validate_segment("name", project.name.as_deref().unwrap_or_default())?;
+    if let Some(category) = &project.category {
+        validate_segment("category", category)?;
+    }
+    if let Some(language) = &project.language {
+        validate_segment("language", language)?;
+    }
+    Ok(())
+}
+
+/// Lowercase `value` and replace every run of characters that are not letters or digits with a single `-`.
+/// The result only contains safe characters, but may still be empty.
+pub fn slug(value: &str) -> String {
+    value
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Check that `path` stays inside `base_dir` once `.` and `..` are resolved, returning the resolved path
+pub fn ensure_within(base_dir: &Path, path: &Path) -> Result<PathBuf> {
+    let base = normalize(base_dir);
+    let resolved = normalize(&base.join(path));
+
+    if resolved == base || !resolved.starts_with(&base) {
+        return Err(Error::PathOutsideBaseDir(path.to_path_buf(), base_dir.to_path_buf()));
+    }
+    Ok(resolved)
+}
+
+/// Resolve `.` and `..` without touching the filesystem. A `..` at the start of a relative path is kept, one above the
+/// root is dropped.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                },
+                Some(Component::RootDir | Component::Prefix(_)) => {},
+                Some(Component::ParentDir | Component::CurDir) | None => normalized.push(component),
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_segment() {
+        for valid in ["fpm", "my project", "fpm.rs", "café", ".config"] {
+            assert!(validate_segment("name", valid).is_ok(), "{valid}");
+        }
+        for invalid in [
+            "", "  ", ".", "..", "../etc", "a/b", "a\\b", "a:b", "what?", "tab\t", "end.", "con", "Com1.txt",
+        ] {
+            assert!(
+                matches!(validate_segment("name", invalid), Err(Error::InvalidName(..))),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_ensure_within() {
+        let base = Path::new("/projects");
+        assert_eq!(
+            ensure_within(base, Path::new("rust/fpm")).ok(),
+            Some(PathBuf::from("/projects/rust/fpm"))
+        );
+        assert_eq!(
+            ensure_within(base, Path::new("rust/../fpm")).ok(),
+            Some(PathBuf::from("/projects/fpm"))
+        );
+        for escaping in ["../../etc", "/etc", "rust/../..", "."] {
+            assert!(
+                matches!(ensure_within(base, Path::new(escaping)), Err(Error::PathOutsideBaseDir(..))),
+                "{escaping}"
+            );
+        }
+
+        // A relative base_dir keeps the folders above it
+        let base = Path::new("../code");
+        assert_eq!(
+            ensure_within(base, Path::new("rust/fpm")).ok(),
+            Some(PathBuf::from("../code/rust/fpm"))
+        );
+        for escaping in ["../fpm", "../../code/fpm", "../../../etc"] {
+            assert!(
+                matches!(ensure_within(base, Path::new(escaping)), Err(Error::PathOutsideBaseDir(..))),
+                "{escaping}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_slug() {
+        assert_eq!(slug("My Cool_Project!"), "my-cool-project");
+        assert_eq!(slug("../../etc"), "etc");
+        assert_eq!(slug("--"), "");
+    }
+}
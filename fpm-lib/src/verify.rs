@@ -0,0 +1,142 @@
+use crate::project::Project;
+use fs_err as fs;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// How many folders up from a missing project to start looking for it, and how deep to look from there.
+///
+/// Three levels covers a project moving to a different category and language in the default folder layout.
+const SEARCH_DEPTH: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub missing: Vec<MissingProject>,
+}
+
+/// A project whose directory no longer exists, along with folders that could be where it went
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MissingProject {
+    pub rowid: Option<i64>,
+    pub name: Option<String>,
+    pub directory: Option<PathBuf>,
+    pub suggestions: Vec<PathBuf>,
+}
+
+/// Check that the directory of every project still exists
+pub fn verify_projects(projects: &[Project]) -> VerifyReport {
+    let missing = projects
+        .iter()
+        .filter(|project| !project.directory.as_ref().is_some_and(|dir| dir.is_dir()))
+        .map(|project| MissingProject {
+            rowid: project.rowid,
+            name: project.name.clone(),
+            directory: project.directory.clone(),
+            suggestions: match &project.directory {
+                Some(dir) => find_similar(dir, project.name.as_deref()),
+                None => vec![],
+            },
+        })
+        .collect();
+
+    VerifyReport {
+        checked: projects.len(),
+        missing,
+    }
+}
+
+/// Look around `missing` for folders with a name close to the project name or the old folder name.
+///
+/// Closeness is measured with the Levenshtein distance, closest matches come first.
+pub fn find_similar(missing: &Path, name: Option<&str>) -> Vec<PathBuf> {
+    let mut targets = vec![];
+    if let Some(name) = name {
+        targets.push(name.to_lowercase());
+    }
+    if let Some(file_name) = missing.file_name() {
+        targets.push(file_name.to_string_lossy().to_lowercase());
+    }
+    if targets.is_empty() {
+        return vec![];
+    }
+
+    let Some(root) = missing.ancestors().skip(SEARCH_DEPTH).find(|dir| dir.is_dir()) else {
+        return vec![];
+    };
+
+    let mut matches = vec![];
+    search(root, &targets, SEARCH_DEPTH, &mut matches);
+    matches.sort();
+    matches.dedup();
+    matches.sort_by_key(|(distance, _)| *distance);
+
+    matches.into_iter().map(|(_, path)| path).collect()
+}
+
+fn search(dir: &Path, targets: &[String], depth: usize, matches: &mut Vec<(usize, PathBuf)>) {
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+        let file_name = entry.file_name().to_string_lossy().to_lowercase();
+        if file_name.starts_with('.') {
+            continue;
+        }
+
+        let distance = targets
+            .iter()
+            .map(|target| strsim::levenshtein(target, &file_name))
+            .min()
+            .unwrap_or(usize::MAX);
+        let max_distance = (file_name.chars().count() / 3).max(1);
+
+        if distance <= max_distance {
+            matches.push((distance, entry.path()));
+        } else {
+            search(&entry.path(), targets, depth - 1, matches);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_verify_projects() -> Result<()> {
+        let root = PathBuf::from("./tests/test_files/verify");
+        let _ = std::fs::remove_dir_all(&root);
+
+        let personal = root.join("personal").join("rust");
+        let work = root.join("work").join("rust");
+        fs::create_dir_all(personal.join("present"))?;
+        fs::create_dir_all(personal.join("my-projekt"))?;
+        fs::create_dir_all(personal.join("unrelated"))?;
+        fs::create_dir_all(work.join("my-proj"))?;
+
+        let mut present = Project::new(Some("present".to_owned()), None, vec![], None, None);
+        present.directory = Some(personal.join("present"));
+        let mut moved = Project::new(Some("my-proj".to_owned()), None, vec![], None, None);
+        moved.directory = Some(personal.join("my-proj"));
+
+        let report = verify_projects(&[present, moved]);
+
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].name.as_deref(), Some("my-proj"));
+        assert_eq!(
+            report.missing[0].suggestions,
+            vec![work.join("my-proj"), personal.join("my-projekt")]
+        );
+
+        Ok(())
+    }
+}